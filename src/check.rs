@@ -0,0 +1,655 @@
+use crate::model::{Column, SpannerType};
+use convert_case::{Case, Casing};
+
+/// Translates the clause of a `CHECK` constraint into a Rust boolean expression over the fields of `self`.
+///
+/// Supported are comparisons, `BETWEEN`, `IN` lists, `IS [NOT] NULL`, `AND` / `OR` / `NOT` and `LENGTH` of
+/// INT64, FLOAT32, FLOAT64, BOOL, STRING and BYTES columns. Returns `None` for anything else.
+/// Like the database, the expression uses three-valued logic: a comparison with a `NULL` column is
+/// unknown (`None`), `AND` / `OR` / `NOT` combine unknowns as in SQL, and the constraint is only
+/// violated when the whole clause is `FALSE`.
+pub fn to_rust_expression(check_clause: &str, columns: &[Column]) -> Option<String> {
+    let tokens = tokenize(check_clause)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        columns,
+        bindings: 0,
+    };
+    let expr = parser.parse_or()?;
+    if parser.position != parser.tokens.len() || expr.ty != Type::Bool {
+        return None;
+    }
+    if expr.nullable {
+        Some(format!("{} != Some(false)", group(&expr.code)))
+    } else {
+        Some(expr.code)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(String),
+    Float(String),
+    Str(String),
+    Op(String),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(clause: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = clause.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            let end = chars[i + 1..].iter().position(|v| *v == c)? + i + 1;
+            let value: String = chars[i + 1..end].iter().collect();
+            if value.contains('\\') {
+                return None;
+            }
+            tokens.push(Token::Str(value));
+            i = end + 1;
+        } else if c == '`' {
+            let end = chars[i + 1..].iter().position(|v| *v == '`')? + i + 1;
+            tokens.push(Token::Ident(chars[i + 1..end].iter().collect()));
+            i = end + 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            let value: String = chars[start..i].iter().collect();
+            if value.parse::<i64>().is_ok() {
+                tokens.push(Token::Int(value));
+            } else if value.parse::<f64>().is_ok() {
+                tokens.push(Token::Float(value));
+            } else {
+                return None;
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            if ["<=", ">=", "!=", "<>"].contains(&two.as_str()) {
+                tokens.push(Token::Op(two));
+                i += 2;
+            } else if ['<', '>', '=', '-'].contains(&c) {
+                tokens.push(Token::Op(c.to_string()));
+                i += 1;
+            } else {
+                return None;
+            }
+        }
+    }
+    Some(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Type {
+    Int,
+    Float,
+    Bool,
+    String,
+    Bytes,
+}
+
+struct Expr {
+    code: String,
+    ty: Type,
+    /// whether `code` is an `Option` which is `None` for SQL `NULL`
+    nullable: bool,
+}
+
+impl Expr {
+    fn new(code: String, ty: Type) -> Self {
+        Self {
+            code,
+            ty,
+            nullable: false,
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    columns: &'a [Column],
+    /// number of closure parameters bound so far, to name them uniquely
+    bindings: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(v)) if v.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Option<()> {
+        (self.next()? == token).then_some(())
+    }
+
+    fn parse_or(&mut self) -> Option<Expr> {
+        let mut left = self.parse_and()?;
+        while self.keyword("OR") {
+            let right = self.parse_and()?;
+            left = logical(left, "||", right)?;
+        }
+        Some(left)
+    }
+
+    fn parse_and(&mut self) -> Option<Expr> {
+        let mut left = self.parse_not()?;
+        while self.keyword("AND") {
+            let right = self.parse_not()?;
+            left = logical(left, "&&", right)?;
+        }
+        Some(left)
+    }
+
+    fn parse_not(&mut self) -> Option<Expr> {
+        if self.keyword("NOT") {
+            let expr = self.parse_not()?;
+            if expr.ty != Type::Bool {
+                return None;
+            }
+            return Some(self.not_if(true, expr));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Option<Expr> {
+        let left = self.parse_operand()?;
+        if let Some(Token::Op(op)) = self.peek().cloned() {
+            self.position += 1;
+            let right = self.parse_operand()?;
+            let op = match op.as_str() {
+                "=" => "==",
+                "<>" => "!=",
+                "-" => return None,
+                v => v,
+            };
+            return self.compare(left, op, right);
+        }
+        if self.keyword("IS") {
+            let negated = self.keyword("NOT");
+            if !self.keyword("NULL") {
+                return None;
+            }
+            let code = match (left.nullable, negated) {
+                (true, false) => format!("{}.is_none()", group(&left.code)),
+                (true, true) => format!("{}.is_some()", group(&left.code)),
+                (false, negated) => negated.to_string(),
+            };
+            return Some(Expr::new(code, Type::Bool));
+        }
+        let negated = self.keyword("NOT");
+        if self.keyword("BETWEEN") {
+            let low = self.parse_operand()?;
+            if !self.keyword("AND") {
+                return None;
+            }
+            let high = self.parse_operand()?;
+            let low = self.compare(left_copy(&left), ">=", low)?;
+            let high = self.compare(left, "<=", high)?;
+            let between = logical(low, "&&", high)?;
+            return Some(self.not_if(negated, between));
+        }
+        if self.keyword("IN") {
+            self.expect(Token::LParen)?;
+            let mut values = vec![];
+            loop {
+                let value = self.parse_operand()?;
+                let (value, _) = self.coerce(value, left_copy(&left))?;
+                if value.ty != left.ty || value.nullable {
+                    return None;
+                }
+                values.push(value.code);
+                match self.next()? {
+                    Token::Comma => continue,
+                    Token::RParen => break,
+                    _ => return None,
+                }
+            }
+            let values = values.join(", ");
+            let contains = self.lift(vec![left], Type::Bool, |v| {
+                format!("[{}].contains(&{})", values, v[0])
+            });
+            return Some(self.not_if(negated, contains));
+        }
+        if negated {
+            return None;
+        }
+        Some(left)
+    }
+
+    fn parse_operand(&mut self) -> Option<Expr> {
+        match self.next()? {
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Some(expr)
+            }
+            Token::Op(op) if op == "-" => match self.next()? {
+                Token::Int(v) => Some(Expr::new(format!("-{}", v), Type::Int)),
+                Token::Float(v) => Some(float_literal(&format!("-{}", v))),
+                _ => None,
+            },
+            Token::Int(v) => Some(Expr::new(v, Type::Int)),
+            Token::Float(v) => Some(float_literal(&v)),
+            Token::Str(v) => Some(Expr::new(format!("{:?}", v), Type::String)),
+            Token::Ident(v)
+                if v.eq_ignore_ascii_case("TRUE") || v.eq_ignore_ascii_case("FALSE") =>
+            {
+                Some(Expr::new(v.to_lowercase(), Type::Bool))
+            }
+            Token::Ident(v) if self.peek() == Some(&Token::LParen) => {
+                self.position += 1;
+                let arg = self.parse_operand()?;
+                self.expect(Token::RParen)?;
+                let length = match (v.to_uppercase().as_str(), arg.ty) {
+                    ("LENGTH" | "CHAR_LENGTH" | "CHARACTER_LENGTH", Type::String) => {
+                        "chars().count()"
+                    }
+                    ("LENGTH" | "BYTE_LENGTH", Type::Bytes) | ("BYTE_LENGTH", Type::String) => {
+                        "len()"
+                    }
+                    _ => return None,
+                };
+                Some(self.lift(vec![arg], Type::Int, |v| {
+                    format!("{}.{} as i64", v[0], length)
+                }))
+            }
+            Token::Ident(v) => self.column(&v),
+            _ => None,
+        }
+    }
+
+    fn column(&mut self, name: &str) -> Option<Expr> {
+        let column = self
+            .columns
+            .iter()
+            .find(|c| c.column_name.eq_ignore_ascii_case(name))?;
+        let ty = match column.parsed_type {
+            SpannerType::Int64 => Type::Int,
+            SpannerType::Float32 | SpannerType::Float64 => Type::Float,
+            SpannerType::Bool => Type::Bool,
+            SpannerType::String { .. } => Type::String,
+            SpannerType::Bytes { .. } => Type::Bytes,
+            _ => return None,
+        };
+        let field = format!("self.{}", column.column_name.to_case(Case::Snake));
+        let code = match (ty, &column.parsed_type, column.nullable) {
+            (Type::String, _, false) => format!("{}.as_str()", field),
+            (Type::Bytes, _, false) => format!("{}.as_slice()", field),
+            (Type::String | Type::Bytes, _, true) => format!("{}.as_deref()", field),
+            _ => field,
+        };
+        Some(Expr {
+            code,
+            ty,
+            nullable: column.nullable,
+        })
+    }
+
+    /// Applies `f` to the codes of `args`. When some of them are nullable, they are unwrapped
+    /// by `map` (and `zip`) so that the result is `None` if any of them is `NULL`.
+    fn lift(&mut self, args: Vec<Expr>, ty: Type, f: impl FnOnce(&[String]) -> String) -> Expr {
+        let mut options: Vec<String> = vec![];
+        let mut pattern = String::new();
+        let mut codes = vec![];
+        for arg in args {
+            if !arg.nullable {
+                codes.push(arg.code);
+                continue;
+            }
+            let name = format!("v{}", self.bindings);
+            self.bindings += 1;
+            pattern = if options.is_empty() {
+                name.clone()
+            } else {
+                format!("({}, {})", pattern, name)
+            };
+            options.push(group(&arg.code));
+            codes.push(name);
+        }
+        let body = f(&codes);
+        match options.split_first() {
+            None => Expr::new(body, ty),
+            Some((first, rest)) => {
+                let mut option = first.clone();
+                for other in rest {
+                    option = format!("{}.zip({})", option, other);
+                }
+                Expr {
+                    code: format!("{}.map(|{}| {})", option, pattern, body),
+                    ty,
+                    nullable: true,
+                }
+            }
+        }
+    }
+
+    fn not_if(&mut self, negated: bool, expr: Expr) -> Expr {
+        if negated {
+            self.lift(vec![expr], Type::Bool, |v| format!("!{}", group(&v[0])))
+        } else {
+            expr
+        }
+    }
+
+    /// Aligns INT64 and FLOAT64 operands and rejects other mismatches.
+    fn coerce(&mut self, left: Expr, right: Expr) -> Option<(Expr, Expr)> {
+        let to_float = |parser: &mut Self, expr: Expr| {
+            let nullable = expr.nullable;
+            parser.lift(vec![expr], Type::Float, |v| {
+                if nullable {
+                    format!("{} as f64", v[0])
+                } else {
+                    format!("({} as f64)", v[0])
+                }
+            })
+        };
+        match (left.ty, right.ty) {
+            (l, r) if l == r => Some((left, right)),
+            (Type::Int, Type::Float) => Some((to_float(self, left), right)),
+            (Type::Float, Type::Int) => Some((left, to_float(self, right))),
+            _ => None,
+        }
+    }
+
+    fn compare(&mut self, left: Expr, op: &str, right: Expr) -> Option<Expr> {
+        let (left, right) = self.coerce(left, right)?;
+        if left.ty == Type::Bytes && op != "==" && op != "!=" {
+            return None;
+        }
+        Some(self.lift(vec![left, right], Type::Bool, |v| {
+            format!("{} {} {}", group(&v[0]), op, group(&v[1]))
+        }))
+    }
+}
+
+/// Writes a GoogleSQL float literal such as `.5`, `5.` or `5E3` as a Rust one such as `0.5`, `5.0` or `5.0E3`.
+fn float_literal(v: &str) -> Expr {
+    let (sign, v) = match v.strip_prefix('-') {
+        Some(v) => ("-", v),
+        None => ("", v),
+    };
+    let (mantissa, exponent) = match v.find(['e', 'E']) {
+        Some(i) => v.split_at(i),
+        None => (v, ""),
+    };
+    let mantissa = match mantissa.split_once('.') {
+        Some((int, fraction)) => format!(
+            "{}.{}",
+            if int.is_empty() { "0" } else { int },
+            if fraction.is_empty() { "0" } else { fraction }
+        ),
+        None => format!("{}.0", mantissa),
+    };
+    Expr::new(format!("{}{}{}", sign, mantissa, exponent), Type::Float)
+}
+
+fn left_copy(expr: &Expr) -> Expr {
+    Expr {
+        code: expr.code.clone(),
+        ty: expr.ty,
+        nullable: expr.nullable,
+    }
+}
+
+/// Wraps `code` in parentheses unless it is a single term.
+fn group(code: &str) -> String {
+    if top_level(code).any(|c| c == ' ') {
+        format!("({})", code)
+    } else {
+        code.to_string()
+    }
+}
+
+/// Yields the characters of `code` that are not nested in brackets.
+fn top_level(code: &str) -> impl Iterator<Item = char> + '_ {
+    let mut depth = 0;
+    code.chars().filter(move |c| {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => return depth == 0,
+        }
+        false
+    })
+}
+
+/// `&&` binds tighter than `||` in both SQL and Rust, so only `||` operands of `&&` need grouping.
+/// With a nullable operand, `FALSE AND NULL` is `FALSE` and `TRUE OR NULL` is `TRUE` as in SQL.
+fn logical(left: Expr, op: &str, right: Expr) -> Option<Expr> {
+    if left.ty != Type::Bool || right.ty != Type::Bool {
+        return None;
+    }
+    if left.nullable || right.nullable {
+        let option = |expr: Expr| {
+            if expr.nullable {
+                expr.code
+            } else {
+                format!("Some({})", expr.code)
+            }
+        };
+        let (dominant, other) = if op == "&&" {
+            ("false", "true")
+        } else {
+            ("true", "false")
+        };
+        let code = format!(
+            "match ({}, {}) {{ (Some({d}), _) | (_, Some({d})) => Some({d}), (Some({o}), Some({o})) => Some({o}), _ => None }}",
+            option(left),
+            option(right),
+            d = dominant,
+            o = other
+        );
+        return Some(Expr {
+            code,
+            ty: Type::Bool,
+            nullable: true,
+        });
+    }
+    let operand = |code: &str| {
+        let or = top_level(code).collect::<String>().contains(" || ");
+        if op == "&&" && or {
+            format!("({})", code)
+        } else {
+            code.to_string()
+        }
+    };
+    Some(Expr::new(
+        format!("{} {} {}", operand(&left.code), op, operand(&right.code)),
+        Type::Bool,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_rust_expression;
+    use crate::model::Column;
+
+    fn translate(check_clause: &str) -> Option<String> {
+        let column = |name: &str, spanner_type: &str, nullable: bool| {
+            Column::new(
+                name.to_string(),
+                1,
+                spanner_type.to_string(),
+                nullable,
+                false,
+                false,
+                false,
+                None,
+            )
+        };
+        let columns = vec![
+            column("A", "BOOL", false),
+            column("B", "BOOL", false),
+            column("C", "BOOL", false),
+            column("Flag", "BOOL", true),
+            column("Level", "INT64", false),
+            column("Rank", "INT64", true),
+            column("Price", "FLOAT64", false),
+            column("Score", "FLOAT32", true),
+            column("Name", "STRING(10)", false),
+            column("Nickname", "STRING(10)", true),
+            column("Data", "BYTES(10)", false),
+            column("Tags", "ARRAY<INT64>", false),
+        ];
+        to_rust_expression(check_clause, &columns)
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            translate("A AND B OR C").unwrap(),
+            "self.a && self.b || self.c"
+        );
+        assert_eq!(
+            translate("A OR B AND C").unwrap(),
+            "self.a || self.b && self.c"
+        );
+        assert_eq!(
+            translate("(A OR B) AND C").unwrap(),
+            "(self.a || self.b) && self.c"
+        );
+        assert_eq!(translate("NOT A AND B").unwrap(), "!self.a && self.b");
+    }
+
+    #[test]
+    fn test_nullable() {
+        assert_eq!(
+            translate("NOT Flag").unwrap(),
+            "self.flag.map(|v0| !v0) != Some(false)"
+        );
+        assert_eq!(
+            translate("Rank NOT BETWEEN 1 AND 10").unwrap(),
+            "(match (self.rank.map(|v0| v0 >= 1), self.rank.map(|v1| v1 <= 10)) { (Some(false), _) | (_, Some(false)) => Some(false), (Some(true), Some(true)) => Some(true), _ => None }).map(|v2| !v2) != Some(false)"
+        );
+        assert_eq!(
+            translate("Rank > 0 AND Level > 0").unwrap(),
+            "(match (self.rank.map(|v0| v0 > 0), Some(self.level > 0)) { (Some(false), _) | (_, Some(false)) => Some(false), (Some(true), Some(true)) => Some(true), _ => None }) != Some(false)"
+        );
+        assert_eq!(
+            translate("Rank > 0 OR Flag").unwrap(),
+            "(match (self.rank.map(|v0| v0 > 0), self.flag) { (Some(true), _) | (_, Some(true)) => Some(true), (Some(false), Some(false)) => Some(false), _ => None }) != Some(false)"
+        );
+    }
+
+    #[test]
+    fn test_is_null() {
+        assert_eq!(
+            translate("Rank > 0 OR Rank IS NULL").unwrap(),
+            "(match (self.rank.map(|v0| v0 > 0), Some(self.rank.is_none())) { (Some(true), _) | (_, Some(true)) => Some(true), (Some(false), Some(false)) => Some(false), _ => None }) != Some(false)"
+        );
+        assert_eq!(
+            translate("Rank IS NOT NULL").unwrap(),
+            "self.rank.is_some()"
+        );
+        assert_eq!(translate("Level IS NULL").unwrap(), "false");
+        assert_eq!(translate("Level IS NOT NULL").unwrap(), "true");
+        assert!(translate("Rank IS 1").is_none());
+    }
+
+    #[test]
+    fn test_in_and_between() {
+        assert_eq!(
+            translate("Level IN (1, 2, 3)").unwrap(),
+            "[1, 2, 3].contains(&self.level)"
+        );
+        assert_eq!(
+            translate("Rank NOT IN (1, 2)").unwrap(),
+            "self.rank.map(|v0| [1, 2].contains(&v0)).map(|v1| !v1) != Some(false)"
+        );
+        assert_eq!(
+            translate("Level BETWEEN 0 AND 100").unwrap(),
+            "self.level >= 0 && self.level <= 100"
+        );
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(
+            translate("LENGTH(Name) <= 5").unwrap(),
+            "(self.name.as_str().chars().count() as i64) <= 5"
+        );
+        assert_eq!(
+            translate("CHAR_LENGTH(Nickname) > 0").unwrap(),
+            "self.nickname.as_deref().map(|v0| v0.chars().count() as i64).map(|v1| v1 > 0) != Some(false)"
+        );
+        assert_eq!(
+            translate("BYTE_LENGTH(Name) < 20").unwrap(),
+            "(self.name.as_str().len() as i64) < 20"
+        );
+        assert_eq!(
+            translate("LENGTH(Data) = 4").unwrap(),
+            "(self.data.as_slice().len() as i64) == 4"
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(
+            translate("Price > Level").unwrap(),
+            "self.price > (self.level as f64)"
+        );
+        assert_eq!(
+            translate("Price IN (1, 2.5)").unwrap(),
+            "[(1 as f64), 2.5].contains(&self.price)"
+        );
+        assert_eq!(
+            translate("Score > .5").unwrap(),
+            "self.score.map(|v0| v0 > 0.5) != Some(false)"
+        );
+        assert_eq!(translate("Price < 5.").unwrap(), "self.price < 5.0");
+        assert_eq!(translate("Price > -.25").unwrap(), "self.price > -0.25");
+        assert_eq!(translate("Price >= 1.5e3").unwrap(), "self.price >= 1.5e3");
+        assert_eq!(
+            translate("Level < 1E3").unwrap(),
+            "(self.level as f64) < 1.0E3"
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(translate("Name > 1").is_none());
+        assert!(translate("ARRAY_LENGTH(Tags) < 3").is_none());
+        assert!(translate("Level > 0 AND").is_none());
+        assert!(translate("Unknown > 0").is_none());
+    }
+}
@@ -108,7 +108,7 @@ impl TableGenerator {
     async fn generate_multi(
         &self,
         handlebars: &Handlebars<'_>,
-        tables: &Vec<Table>,
+        tables: &[Table],
         template_string: &str,
         file_name: &str,
         output_dir: &str,
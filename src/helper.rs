@@ -1,49 +1,139 @@
+use crate::model::SpannerType;
 use convert_case::{Case, Casing};
 use handlebars::handlebars_helper;
 use handlebars::Handlebars;
+use serde_json::Value;
 
-fn rust_type_helper(spanner_type: String) -> String {
-    let should_wrap_array = spanner_type.starts_with("ARRAY");
-    let spanner_type = if should_wrap_array {
-        spanner_type.replace("ARRAY<", "").replace('>', "")
-    } else {
-        spanner_type
-    };
-
-    let v = if spanner_type == "BOOL" {
-        "bool"
-    } else if spanner_type == "DATE" {
-        "time::Date"
-    } else if spanner_type == "TIMESTAMP" {
-        "time::OffsetDateTime"
-    } else if spanner_type == "FLOAT64" {
-        "f64"
-    } else if spanner_type == "NUMERIC" {
-        "google_cloud_spanner::bigdecimal::BigDecimal"
-    } else if spanner_type.starts_with("BYTES") {
-        "Vec<u8>"
-    } else if spanner_type == "INT64" {
-        "i64"
-    } else {
-        "String"
-    };
-    if should_wrap_array {
-        return format!("Vec<{}>", v);
+/// Accepts the parsed type of a column as well as a raw `SPANNER_TYPE` string.
+fn spanner_type(v: &Value) -> SpannerType {
+    match v.as_str() {
+        Some(v) => v.into(),
+        None => serde_json::from_value(v.clone()).unwrap_or(SpannerType::Other {
+            name: v.to_string(),
+        }),
     }
-    v.to_string()
 }
 
-fn rust_default_helper(nullable: bool, spanner_type: String) -> String {
+fn rust_type_helper(spanner_type: &SpannerType) -> String {
+    match spanner_type {
+        SpannerType::Bool => "bool".to_string(),
+        SpannerType::Date => "time::Date".to_string(),
+        SpannerType::Timestamp => "time::OffsetDateTime".to_string(),
+        // google-cloud-spanner has no conversions for f32, so FLOAT32 is read and written as f64
+        SpannerType::Float32 | SpannerType::Float64 => "f64".to_string(),
+        SpannerType::Numeric => "google_cloud_spanner::bigdecimal::BigDecimal".to_string(),
+        SpannerType::Bytes { .. } => "Vec<u8>".to_string(),
+        SpannerType::Int64 => "i64".to_string(),
+        SpannerType::Proto { .. } => "Vec<u8>".to_string(),
+        SpannerType::Enum { .. } => "i64".to_string(),
+        SpannerType::Array { element, .. } => format!("Vec<{}>", rust_type_helper(element)),
+        _ => "String".to_string(),
+    }
+}
+
+/// Whether the Rust type of a column is `Copy`, so that it is copied instead of cloned.
+fn rust_copy_helper(spanner_type: &SpannerType) -> bool {
+    matches!(
+        spanner_type,
+        SpannerType::Bool
+            | SpannerType::Int64
+            | SpannerType::Float32
+            | SpannerType::Float64
+            | SpannerType::Date
+            | SpannerType::Timestamp
+            | SpannerType::Enum { .. }
+    )
+}
+
+/// Whether values of a column can be compared and ordered, which excludes `ARRAY`, `JSON`, `STRUCT` and `PROTO`.
+fn comparable_helper(spanner_type: &SpannerType) -> bool {
+    !matches!(
+        spanner_type,
+        SpannerType::Array { .. }
+            | SpannerType::Json
+            | SpannerType::Struct { .. }
+            | SpannerType::Proto { .. }
+            | SpannerType::Other { .. }
+    )
+}
+
+fn rust_default_helper(
+    nullable: bool,
+    spanner_type: &SpannerType,
+    column_default: Option<&str>,
+) -> String {
+    if let Some(v) = column_default.and_then(|v| rust_literal(spanner_type, v)) {
+        return if nullable { format!("Some({})", v) } else { v };
+    }
     if !nullable {
-        if spanner_type == "DATE" {
-            return "time::OffsetDateTime::now_utc().date()".to_string();
-        } else if spanner_type == "TIMESTAMP" {
-            return "time::OffsetDateTime::now_utc()".to_string();
+        match spanner_type {
+            SpannerType::Date => return "time::OffsetDateTime::now_utc().date()".to_string(),
+            SpannerType::Timestamp => return "time::OffsetDateTime::now_utc()".to_string(),
+            _ => {}
         }
     }
     "Default::default()".to_string()
 }
 
+/// Translates the SQL expression of a `DEFAULT` clause into a Rust expression.
+/// Returns `None` when the expression is not a literal or a well-known function.
+fn rust_literal(spanner_type: &SpannerType, column_default: &str) -> Option<String> {
+    let mut v = column_default.trim();
+    while v.starts_with('(') && v.ends_with(')') {
+        v = v[1..v.len() - 1].trim();
+    }
+    let function = v.trim_end_matches("()").to_uppercase();
+
+    match spanner_type {
+        SpannerType::Bool => match v.to_uppercase().as_str() {
+            "TRUE" => Some("true".to_string()),
+            "FALSE" => Some("false".to_string()),
+            _ => None,
+        },
+        SpannerType::Int64 => v.parse::<i64>().ok().map(|v| v.to_string()),
+        SpannerType::Float32 => v
+            .parse::<f32>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .and_then(|_| v.parse::<f64>().ok())
+            .map(|v| format!("{:?}", v)),
+        SpannerType::Float64 => v
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(|v| format!("{:?}", v)),
+        SpannerType::Numeric => {
+            let v = v.strip_prefix("NUMERIC").map(str::trim).unwrap_or(v);
+            let v = v
+                .strip_prefix('\'')
+                .and_then(|v| v.strip_suffix('\''))
+                .unwrap_or(v);
+            v.parse::<f64>()
+                .ok()
+                .map(|_| format!("{:?}.parse().unwrap()", v))
+        }
+        // one quoted literal only, not an expression such as 'a' || 'b'
+        SpannerType::String { .. } => ['\'', '"']
+            .into_iter()
+            .find_map(|quote| {
+                v.strip_prefix(quote)
+                    .and_then(|v| v.strip_suffix(quote))
+                    .filter(|v| !v.contains(quote))
+            })
+            .filter(|v| !v.contains('\\'))
+            .map(|v| format!("{:?}.to_string()", v)),
+        SpannerType::Timestamp => match function.as_str() {
+            "CURRENT_TIMESTAMP" => Some("time::OffsetDateTime::now_utc()".to_string()),
+            _ => None,
+        },
+        SpannerType::Date => match function.as_str() {
+            "CURRENT_DATE" => Some("time::OffsetDateTime::now_utc().date()".to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn rust_arg_type_helper(v: String) -> String {
     format!("&{}", v.replace("String", "str"))
 }
@@ -60,12 +150,46 @@ fn upper_snake_helper(v: String) -> String {
     v.to_case(Case::UpperSnake)
 }
 
-handlebars_helper!(rust_type: |v: String | rust_type_helper(v));
+fn pascal_helper(v: String) -> String {
+    v.to_case(Case::Pascal)
+}
+
+/// Expression iterating over the values of a column, for validating them one by one.
+fn rust_values_helper(spanner_type: &SpannerType, nullable: bool, field: String) -> String {
+    let array = matches!(spanner_type, SpannerType::Array { .. });
+    match (array, nullable) {
+        (false, false) => format!("std::slice::from_ref(&self.{})", field),
+        (true, true) => format!("self.{}.iter().flatten()", field),
+        _ => format!("self.{}.iter()", field),
+    }
+}
+
+/// Type to cast the parameters of a column to, for the columns whose Rust type binds a different Spanner type:
+/// `FLOAT32` bound as f64, `PROTO` bound as bytes and `ENUM` bound as INT64, or arrays of them. Empty for the other columns.
+fn cast_type_helper(spanner_type: &SpannerType) -> String {
+    match spanner_type {
+        SpannerType::Float32 => "FLOAT32".to_string(),
+        SpannerType::Proto { name, .. } | SpannerType::Enum { name, .. } => format!("`{}`", name),
+        SpannerType::Array { element, .. } => match cast_type_helper(element) {
+            v if v.is_empty() => v,
+            v => format!("ARRAY<{}>", v),
+        },
+        _ => "".to_string(),
+    }
+}
+
+handlebars_helper!(rust_type: |v: Json| rust_type_helper(&spanner_type(v)));
 handlebars_helper!(rust_arg_type: |v: String | rust_arg_type_helper(v));
 handlebars_helper!(rust_caller_type: |v: String | rust_caller_type_helper(v));
 handlebars_helper!(snake: |v: String | snake_helper(v));
 handlebars_helper!(upper_snake: |v: String | upper_snake_helper(v));
-handlebars_helper!(rust_default: |n: bool, v: String | rust_default_helper(n, v));
+handlebars_helper!(rust_copy: |v: Json| rust_copy_helper(&spanner_type(v)));
+handlebars_helper!(ddl_type: |v: Json| spanner_type(v).to_string());
+handlebars_helper!(pascal: |v: String | pascal_helper(v));
+handlebars_helper!(cast_type: |v: Json| cast_type_helper(&spanner_type(v)));
+handlebars_helper!(comparable: |v: Json| comparable_helper(&spanner_type(v)));
+handlebars_helper!(rust_values: |t: Json, n: bool, f: String| rust_values_helper(&spanner_type(t), n, f));
+handlebars_helper!(rust_default: |n: bool, v: Json, *args| rust_default_helper(n, &spanner_type(v), args.get(2).and_then(|d| d.as_str())));
 
 pub fn register(handlebars: &mut Handlebars) {
     handlebars.register_helper("rust_type", Box::new(rust_type));
@@ -74,4 +198,32 @@ pub fn register(handlebars: &mut Handlebars) {
     handlebars.register_helper("snake", Box::new(snake));
     handlebars.register_helper("upper_snake", Box::new(upper_snake));
     handlebars.register_helper("rust_default", Box::new(rust_default));
+    handlebars.register_helper("rust_values", Box::new(rust_values));
+    handlebars.register_helper("rust_copy", Box::new(rust_copy));
+    handlebars.register_helper("ddl_type", Box::new(ddl_type));
+    handlebars.register_helper("pascal", Box::new(pascal));
+    handlebars.register_helper("comparable", Box::new(comparable));
+    handlebars.register_helper("cast_type", Box::new(cast_type));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rust_literal;
+    use crate::model::SpannerType;
+
+    #[test]
+    fn test_string_literal() {
+        let string = SpannerType::String { max_length: None };
+        assert_eq!(
+            rust_literal(&string, "('abc')").unwrap(),
+            "\"abc\".to_string()"
+        );
+        assert_eq!(
+            rust_literal(&string, "\"it's\"").unwrap(),
+            "\"it's\".to_string()"
+        );
+        assert!(rust_literal(&string, "('a' || 'b')").is_none());
+        assert!(rust_literal(&string, "CONCAT('a', 'b')").is_none());
+        assert!(rust_literal(&string, "'a\\'b'").is_none());
+    }
 }
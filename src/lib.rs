@@ -16,6 +16,8 @@
 //!   - template directory.
 //!   - see template directory [structure](./src/default)
 //!   - if not specified default template are used.
+//!   - a column has `spanner_type`, the raw `SPANNER_TYPE` string such as `STRING(36)`, and `parsed_type`, its parsed form with `kind`, `max_length`, `element` and so on
+//!   - each entry of an index's `columns` is an object with `column_name`, `ordinal_position`, `ordering`, `spanner_type` and `parsed_type` instead of a `[column_name, ordinal_position]` pair, so write `this.column_name` instead of `this.[0]` in custom templates
 //!
 //! * -o
 //!   - output directory
@@ -27,10 +29,23 @@
 //! * -d
 //!   - implements `Default` trait
 //!
+//! * -p
+//!   - maps a `PROTO` or `ENUM` column type to a prost-generated Rust type, e.g. `-p examples.music.SingerInfo=crate::pb::SingerInfo`
+//!   - can be specified multiple times
+//!   - the columns keep their wire types (`Vec<u8>` / `i64`) and get typed accessors such as `singer_info_message()` / `genre_enum()`, or `singer_infos_messages()` / `genres_enums()` for `ARRAY` columns, which require `prost` in your crate
+//!   - prost types cannot be fields themselves, because google-cloud-spanner has no conversions for them
+//!   - the generated DML casts the bytes and INT64 parameters of these columns to their `PROTO` / `ENUM` types
+//!
 //! ### Generated file with default template
 //!
 //! Default template generates the files for [google-cloud-spanner](https://github.com/yoshidan/google-cloud-rust/tree/main/spanner).
 //!
+//! `FLOAT32` and `ARRAY<FLOAT32>` columns are generated as `f64` / `Vec<f64>`, because google-cloud-spanner has no conversions for `f32`. The generated DML casts their parameters back to `FLOAT32`.
+//!
+//! `insert_dml()`, `update_dml()` and `delete_dml()` build DML statements for `ReadWriteTransaction::update` / `batch_update`. Their `THEN RETURN *` variants `insert_dml_returning()`, `update_dml_returning()` and `delete_dml_returning()` must be run with `tx.query` as the only DML of the transaction: google-cloud-spanner 0.26 sends queries with sequence number 0, so Spanner rejects them after an `update` or `batch_update` in the same transaction.
+//!
+//! Each table with a primary key and no `FLOAT32` / `FLOAT64` key column also gets a `<Table>Repository` trait with a Spanner implementation and an in-memory fake for unit tests. The fake reports errors with `google-cloud-gax` status codes, so add `google-cloud-gax` to your dependencies as well.
+//!
 //! ```
 //! //! DON'T EDIT. this code is generated by nene.
 //! use google_cloud_googleapis::spanner::v1::Mutation;
@@ -108,6 +123,8 @@
 //!   #[spanner(name = "NullableString")]
 //!   pub nullable_string: Option<String>,
 //!   #[serde(with = "time::serde::rfc3339")]
+//!   /// Every mutation built from this struct writes the pending commit timestamp instead of this value.
+//!   /// Use `set_commit_timestamp` to fill it in after the commit.
 //!   #[spanner(name = "UpdatedAt",commitTimestamp)]
 //!   pub updated_at: time::OffsetDateTime,
 //! }
@@ -133,7 +150,7 @@
 //!       not_null_array: Default::default(),
 //!       nullable_array: Default::default(),
 //!       nullable_string: Default::default(),
-//!       updated_at: time::OffsetDateTime::now_utc(),
+//!       updated_at: time::OffsetDateTime::UNIX_EPOCH,
 //!     }
 //!   }
 //! }
@@ -189,6 +206,7 @@
 //! }
 //!
 //! ```
+pub mod check;
 pub mod generator;
 pub mod helper;
 pub mod model;
@@ -1,7 +1,7 @@
 use clap::{App, Arg};
 use google_cloud_spanner::client::{Client, ClientConfig};
 use nene::generator::TableGenerator;
-use nene::repository::TableRepository;
+use nene::repository::{parse_proto_types, TableRepository};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -37,20 +37,30 @@ async fn main() -> anyhow::Result<()> {
                 .help("default trait support")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("proto")
+                .short("p")
+                .long("proto")
+                .help("rust type of a PROTO or ENUM column type, e.g. examples.Singer=crate::pb::Singer")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
         .get_matches();
     let input = matches.value_of("input_dir");
     let output = matches.value_of("output_dir").unwrap_or("./gen");
     let json = matches.is_present("json");
     let default = matches.is_present("default");
+    let proto_types = parse_proto_types(matches.values_of("proto").into_iter().flatten())?;
 
     let config = ClientConfig::default().with_auth().await?;
     let client = Client::new(database, config).await?;
-    let repository = TableRepository::new(client, json, default);
+    let repository = TableRepository::new(client, json, default, proto_types);
     let generator = TableGenerator::new(repository);
 
-    if input.is_some() {
+    if let Some(input) = input {
         log::info!("generate from custom template output dir is {}", output);
-        generator.generate(input.unwrap(), output).await
+        generator.generate(input, output).await
     } else {
         log::info!("generate from default template output dir is {}", output);
         generator.generate_default(output).await
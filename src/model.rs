@@ -1,17 +1,300 @@
+use crate::check::to_rust_expression;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Type of a column, parsed from the `SPANNER_TYPE` of `INFORMATION_SCHEMA.COLUMNS`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SpannerType {
+    Bool,
+    Int64,
+    Float32,
+    Float64,
+    Numeric,
+    Json,
+    Date,
+    Timestamp,
+    String {
+        /// `None` for `STRING(MAX)`
+        max_length: Option<u64>,
+    },
+    Bytes {
+        /// `None` for `BYTES(MAX)`
+        max_length: Option<u64>,
+    },
+    Array {
+        element: Box<SpannerType>,
+        /// `N` of `ARRAY<FLOAT32>(vector_length=>N)`
+        vector_length: Option<u64>,
+    },
+    Struct {
+        fields: Vec<StructField>,
+    },
+    Proto {
+        name: String,
+        /// Rust message type configured for `name`
+        rust_type: Option<String>,
+    },
+    Enum {
+        name: String,
+        /// Rust enum type configured for `name`
+        rust_type: Option<String>,
+    },
+    /// a type unknown to nene, kept as is
+    Other {
+        name: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StructField {
+    pub name: Option<String>,
+    pub field_type: SpannerType,
+}
+
+impl SpannerType {
+    /// The `kind` tag, e.g. `STRING` for `STRING(36)`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SpannerType::Bool => "BOOL",
+            SpannerType::Int64 => "INT64",
+            SpannerType::Float32 => "FLOAT32",
+            SpannerType::Float64 => "FLOAT64",
+            SpannerType::Numeric => "NUMERIC",
+            SpannerType::Json => "JSON",
+            SpannerType::Date => "DATE",
+            SpannerType::Timestamp => "TIMESTAMP",
+            SpannerType::String { .. } => "STRING",
+            SpannerType::Bytes { .. } => "BYTES",
+            SpannerType::Array { .. } => "ARRAY",
+            SpannerType::Struct { .. } => "STRUCT",
+            SpannerType::Proto { .. } => "PROTO",
+            SpannerType::Enum { .. } => "ENUM",
+            SpannerType::Other { .. } => "OTHER",
+        }
+    }
+
+    /// The element type of an array, or the type itself.
+    pub fn element(&self) -> &SpannerType {
+        match self {
+            SpannerType::Array { element, .. } => element,
+            v => v,
+        }
+    }
+
+    /// Applies the Rust types configured for `PROTO` and `ENUM` types, keyed by their fully qualified names.
+    pub fn with_rust_types(self, rust_types: &HashMap<String, String>) -> Self {
+        match self {
+            SpannerType::Proto { name, .. } => SpannerType::Proto {
+                rust_type: rust_types.get(&name).cloned(),
+                name,
+            },
+            SpannerType::Enum { name, .. } => SpannerType::Enum {
+                rust_type: rust_types.get(&name).cloned(),
+                name,
+            },
+            SpannerType::Array {
+                element,
+                vector_length,
+            } => SpannerType::Array {
+                element: Box::new(element.with_rust_types(rust_types)),
+                vector_length,
+            },
+            v => v,
+        }
+    }
+
+    pub fn max_length(&self) -> Option<u64> {
+        match self.element() {
+            SpannerType::String { max_length } | SpannerType::Bytes { max_length } => *max_length,
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for SpannerType {
+    fn from(v: &str) -> Self {
+        let v = v.trim();
+        let generic = |prefix: &str| {
+            let upper = v.to_uppercase();
+            (upper.starts_with(prefix) && v.ends_with('>'))
+                .then(|| v[prefix.len()..v.len() - 1].trim())
+        };
+        if let Some((element, options)) = array(v) {
+            let vector_length = options
+                .strip_prefix('(')
+                .and_then(|v| v.strip_suffix(')'))
+                .and_then(|v| v.split_once("=>"))
+                .filter(|(name, _)| name.trim().eq_ignore_ascii_case("vector_length"))
+                .and_then(|(_, length)| length.trim().parse().ok());
+            return SpannerType::Array {
+                element: Box::new(element.into()),
+                vector_length,
+            };
+        }
+        if let Some(fields) = generic("STRUCT<") {
+            let fields = split_top_level(fields)
+                .into_iter()
+                .filter(|v| !v.is_empty())
+                .map(|field| match split_field(field) {
+                    Some((name, field_type)) => StructField {
+                        name: Some(name.to_string()),
+                        field_type: field_type.into(),
+                    },
+                    None => StructField {
+                        name: None,
+                        field_type: field.into(),
+                    },
+                })
+                .collect();
+            return SpannerType::Struct { fields };
+        }
+        if let Some(name) = generic("PROTO<") {
+            return SpannerType::Proto {
+                name: name.to_string(),
+                rust_type: None,
+            };
+        }
+        if let Some(name) = generic("ENUM<") {
+            return SpannerType::Enum {
+                name: name.to_string(),
+                rust_type: None,
+            };
+        }
+        let (base_type, length) = match v.split_once('(') {
+            Some((base_type, length)) => (base_type.trim(), length.trim_end_matches(')').trim()),
+            None => (v, ""),
+        };
+        let max_length = length.parse().ok();
+        match base_type.to_uppercase().as_str() {
+            "BOOL" => SpannerType::Bool,
+            "INT64" => SpannerType::Int64,
+            "FLOAT32" => SpannerType::Float32,
+            "FLOAT64" => SpannerType::Float64,
+            "NUMERIC" => SpannerType::Numeric,
+            "JSON" => SpannerType::Json,
+            "DATE" => SpannerType::Date,
+            "TIMESTAMP" => SpannerType::Timestamp,
+            "STRING" => SpannerType::String { max_length },
+            "BYTES" => SpannerType::Bytes { max_length },
+            _ => SpannerType::Other {
+                name: v.to_string(),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for SpannerType {
+    /// Formats the type as in DDL, e.g. `ARRAY<STRING(MAX)>`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let length = |max_length: &Option<u64>| match max_length {
+            Some(v) => v.to_string(),
+            None => "MAX".to_string(),
+        };
+        match self {
+            SpannerType::String { max_length } => write!(f, "STRING({})", length(max_length)),
+            SpannerType::Bytes { max_length } => write!(f, "BYTES({})", length(max_length)),
+            SpannerType::Array {
+                element,
+                vector_length,
+            } => {
+                write!(f, "ARRAY<{}>", element)?;
+                match vector_length {
+                    Some(v) => write!(f, "(vector_length=>{})", v),
+                    None => Ok(()),
+                }
+            }
+            SpannerType::Struct { fields } => {
+                write!(f, "STRUCT<")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match &field.name {
+                        Some(name) => write!(f, "{} {}", name, field.field_type)?,
+                        None => write!(f, "{}", field.field_type)?,
+                    }
+                }
+                write!(f, ">")
+            }
+            SpannerType::Proto { name, .. } => write!(f, "PROTO<{}>", name),
+            SpannerType::Enum { name, .. } => write!(f, "ENUM<{}>", name),
+            SpannerType::Other { name } => write!(f, "{}", name),
+            v => write!(f, "{}", v.kind()),
+        }
+    }
+}
+
+/// Splits `ARRAY<T>(options)` into `T` and the trailing options.
+fn array(v: &str) -> Option<(&str, &str)> {
+    if !v.to_uppercase().starts_with("ARRAY<") {
+        return None;
+    }
+    let mut depth = 0;
+    for (i, c) in v.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((v["ARRAY<".len()..i].trim(), v[i + 1..].trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits `v` at the commas which are not nested in `<>` or `()`.
+fn split_top_level(v: &str) -> Vec<&str> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut parts = vec![];
+    for (i, c) in v.char_indices() {
+        match c {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(v[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(v[start..].trim());
+    parts
+}
+
+/// Splits a `STRUCT` field into its name and type, `None` when the field has no name.
+fn split_field(field: &str) -> Option<(&str, &str)> {
+    let (name, field_type) = field.split_once(char::is_whitespace)?;
+    let named = !name.contains('<') && !name.contains('(');
+    named.then(|| (name, field_type.trim()))
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Column {
     pub column_name: String,
     pub ordinal_position: i64,
+    /// `SPANNER_TYPE` as in `INFORMATION_SCHEMA.COLUMNS`, e.g. `ARRAY<STRING(36)>`
     pub spanner_type: String,
+    /// `spanner_type` parsed
+    pub parsed_type: SpannerType,
+    /// kind of the column or of its array elements, e.g. `STRING`
+    pub base_type: String,
+    /// declared length of `STRING(N)` / `BYTES(N)`, `None` for `MAX` and other types
+    pub max_length: Option<u64>,
     pub nullable: bool,
     pub primary_key: bool,
     pub generated: bool,
     pub allow_commit_timestamp: bool,
+    /// SQL expression of the `DEFAULT` clause
+    pub column_default: Option<String>,
 }
 
 impl Column {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         column_name: String,
         ordinal_position: i64,
@@ -20,24 +303,132 @@ impl Column {
         primary_key: bool,
         generated: bool,
         allow_commit_timestamp: bool,
+        column_default: Option<String>,
     ) -> Self {
+        let parsed_type = SpannerType::from(spanner_type.as_str());
+        let base_type = parsed_type.element().kind().to_string();
+        let max_length = parsed_type.max_length();
         Self {
             column_name,
             ordinal_position,
+            base_type,
+            max_length,
             spanner_type,
+            parsed_type,
             nullable,
             primary_key,
             generated,
             allow_commit_timestamp,
+            column_default,
         }
     }
+
+    /// Applies the Rust types configured for `PROTO` and `ENUM` types to `parsed_type`.
+    pub fn with_rust_types(mut self, rust_types: &HashMap<String, String>) -> Self {
+        self.parsed_type = self.parsed_type.with_rust_types(rust_types);
+        self
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IndexColumn {
+    pub column_name: String,
+    pub ordinal_position: i64,
+    /// `ASC` or `DESC`
+    pub ordering: String,
+    /// `SPANNER_TYPE` of the column
+    pub spanner_type: String,
+    /// `spanner_type` parsed
+    pub parsed_type: SpannerType,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Index {
     pub index_name: String,
     pub unique: bool,
-    pub columns: Vec<(String, i64)>,
+    pub null_filtered: bool,
+    /// table the index is interleaved in
+    pub parent_table_name: Option<String>,
+    /// key columns in the order of the index key
+    pub columns: Vec<IndexColumn>,
+    /// columns of the `STORING` clause
+    pub storing: Vec<String>,
+    /// whether every column of the table can be read from the index without a back-join
+    pub covering: bool,
+}
+
+impl Index {
+    pub fn new(
+        index_name: String,
+        unique: bool,
+        null_filtered: bool,
+        parent_table_name: Option<String>,
+        columns: Vec<IndexColumn>,
+        storing: Vec<String>,
+        table_columns: &[Column],
+    ) -> Self {
+        let covering = table_columns.iter().all(|c| {
+            c.primary_key
+                || storing.contains(&c.column_name)
+                || columns.iter().any(|v| v.column_name == c.column_name)
+        });
+        Self {
+            index_name,
+            unique,
+            null_filtered,
+            parent_table_name: parent_table_name.filter(|v| !v.is_empty()),
+            columns,
+            storing,
+            covering,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForeignKeyColumn {
+    pub column_name: String,
+    pub referenced_column_name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForeignKey {
+    pub constraint_name: String,
+    pub table_name: String,
+    pub columns: Vec<ForeignKeyColumn>,
+    pub referenced_table_name: String,
+    pub on_delete: String,
+    /// whether an interleaved child references its parent by the parent key, which
+    /// `fetch_<parent>` / `read_<child>_rows` already cover, so no accessors are generated
+    pub interleaved: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CheckConstraint {
+    pub constraint_name: String,
+    pub check_clause: String,
+    /// Rust expression equivalent to `check_clause`, if it could be translated
+    pub rust_expression: Option<String>,
+}
+
+impl CheckConstraint {
+    pub fn new(constraint_name: String, check_clause: String, columns: &[Column]) -> Self {
+        let rust_expression = to_rust_expression(&check_clause, columns);
+        Self {
+            constraint_name,
+            check_clause: check_clause
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            rust_expression,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChildTable {
+    pub table_name: String,
+    pub on_delete_action: Option<String>,
+    pub interleave_type: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -51,20 +442,54 @@ pub struct PrimaryKey {
 pub struct Table {
     pub table_name: String,
     pub parent_table_name: Option<String>,
+    /// `CASCADE` or `NO ACTION` for interleaved tables
+    pub on_delete_action: Option<String>,
+    /// `IN PARENT` or `IN` for interleaved tables
+    pub interleave_type: Option<String>,
+    /// primary key columns of the parent table, which prefix the primary key of this table
+    pub parent_primary_keys: Vec<Column>,
+    /// top-level table of the interleave hierarchy, this table itself when not interleaved
+    pub root_table_name: String,
+    /// number of ancestors in the interleave hierarchy
+    pub depth: usize,
+    /// tables interleaved in this table
+    pub children: Vec<ChildTable>,
+    /// interleaved descendants which `delete_with_children` deletes before the row, deepest first;
+    /// tables interleaved `IN PARENT ... ON DELETE CASCADE` are deleted by the database instead
+    pub deleted_descendants: Vec<ChildTable>,
     pub columns: Vec<Column>,
     pub indexes: Vec<Index>,
+    pub foreign_keys: Vec<ForeignKey>,
+    /// foreign keys of other tables referencing this table
+    pub referenced_by: Vec<ForeignKey>,
+    pub check_constraints: Vec<CheckConstraint>,
     pub primary_keys: Vec<PrimaryKey>,
     pub composite_key: bool,
+    /// whether the key struct can derive `Eq`, `Hash` and `Ord`, i.e. no key column is a float
+    pub key_ord: bool,
+    /// whether the key struct can implement `Display` and `FromStr`, i.e. every key column is a non-null INT64, BOOL or STRING
+    pub key_display: bool,
+    /// whether any column has a `DEFAULT` clause
+    pub has_column_default: bool,
+    /// whether any column allows the commit timestamp
+    pub has_commit_timestamp: bool,
+    /// columns written by `INSERT`, i.e. all but generated columns
+    pub insertable_columns: Vec<Column>,
+    /// columns written by `UPDATE`, i.e. neither key nor generated columns
+    pub updatable_columns: Vec<Column>,
     pub json: bool,
     pub default: bool,
 }
 
 impl Table {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         table_name: String,
         parent_table_name: Option<String>,
         columns: Vec<Column>,
         indexes: Vec<Index>,
+        foreign_keys: Vec<ForeignKey>,
+        check_constraints: Vec<CheckConstraint>,
         json: bool,
         default: bool,
     ) -> Self {
@@ -89,17 +514,154 @@ impl Table {
                 last: false,
             })
         }
-        primary_keys_with_rest[primary_keys.len() - 1].last = true;
+        // tables declared with `PRIMARY KEY ()` hold at most one row and have no key columns
+        if let Some(last) = primary_keys_with_rest.last_mut() {
+            last.last = true;
+        }
 
+        let key_ord = primary_keys
+            .iter()
+            .all(|c| !matches!(c.parsed_type, SpannerType::Float32 | SpannerType::Float64));
+        let key_display = primary_keys.iter().all(|c| {
+            !c.nullable
+                && matches!(
+                    c.parsed_type,
+                    SpannerType::Int64 | SpannerType::Bool | SpannerType::String { .. }
+                )
+        });
+        let has_column_default = columns.iter().any(|c| c.column_default.is_some());
+        let has_commit_timestamp = columns.iter().any(|c| c.allow_commit_timestamp);
+        let insertable_columns: Vec<Column> =
+            columns.iter().filter(|c| !c.generated).cloned().collect();
+        let updatable_columns = insertable_columns
+            .iter()
+            .filter(|c| !c.primary_key)
+            .cloned()
+            .collect();
         Self {
+            root_table_name: table_name.clone(),
             table_name,
             parent_table_name,
+            on_delete_action: None,
+            interleave_type: None,
+            parent_primary_keys: vec![],
+            depth: 0,
+            children: vec![],
+            deleted_descendants: vec![],
             columns,
             indexes,
+            foreign_keys,
+            referenced_by: vec![],
+            check_constraints,
             composite_key: primary_keys.len() > 1,
+            key_ord,
+            key_display,
+            has_column_default,
+            has_commit_timestamp,
+            insertable_columns,
+            updatable_columns,
             primary_keys: primary_keys_with_rest,
             json,
             default,
         }
     }
+
+    /// Fills in the fields that depend on the other tables in the schema.
+    pub fn resolve_relations(tables: &mut [Table]) {
+        let primary_keys: Vec<(String, Vec<Column>)> = tables
+            .iter()
+            .map(|t| {
+                let columns = t.columns.iter().filter(|c| c.primary_key).cloned();
+                (t.table_name.clone(), columns.collect())
+            })
+            .collect();
+        for table in tables.iter_mut() {
+            let parent = primary_keys
+                .iter()
+                .find(|(name, _)| Some(name) == table.parent_table_name.as_ref());
+            for fk in table.foreign_keys.iter_mut() {
+                fk.interleaved = parent.is_some_and(|(name, key)| {
+                    fk.referenced_table_name == *name
+                        && fk.columns.len() == key.len()
+                        && fk.columns.iter().all(|c| {
+                            c.column_name == c.referenced_column_name
+                                && key.iter().any(|k| k.column_name == c.column_name)
+                        })
+                });
+            }
+        }
+        let foreign_keys: Vec<ForeignKey> = tables
+            .iter()
+            .flat_map(|t| t.foreign_keys.iter().cloned())
+            .collect();
+        let parents: Vec<(ChildTable, String)> = tables
+            .iter()
+            .filter_map(|t| {
+                let parent = t.parent_table_name.as_ref()?;
+                let child = ChildTable {
+                    table_name: t.table_name.clone(),
+                    on_delete_action: t.on_delete_action.clone(),
+                    interleave_type: t.interleave_type.clone(),
+                };
+                Some((child, parent.clone()))
+            })
+            .collect();
+
+        for table in tables.iter_mut() {
+            table.referenced_by = foreign_keys
+                .iter()
+                .filter(|fk| fk.referenced_table_name == table.table_name)
+                .cloned()
+                .collect();
+            table.children = parents
+                .iter()
+                .filter(|(_, parent)| *parent == table.table_name)
+                .map(|(child, _)| child.clone())
+                .collect();
+            table
+                .children
+                .sort_by(|a, b| a.table_name.cmp(&b.table_name));
+            table.deleted_descendants = vec![];
+            collect_deleted_descendants(
+                &table.table_name,
+                &parents,
+                &mut table.deleted_descendants,
+            );
+            if let Some(parent) = &table.parent_table_name {
+                if let Some((_, columns)) = primary_keys.iter().find(|(name, _)| name == parent) {
+                    table.parent_primary_keys = columns.clone();
+                }
+            }
+            while let Some((_, parent)) = parents
+                .iter()
+                .find(|(child, _)| child.table_name == table.root_table_name)
+            {
+                table.root_table_name = parent.clone();
+                table.depth += 1;
+            }
+        }
+    }
+}
+
+/// Appends the descendants of `table_name` which must be deleted explicitly, each after its own descendants.
+/// Descendants below a `CASCADE` child are still visited, as a `NO ACTION` grandchild blocks the cascade.
+fn collect_deleted_descendants(
+    table_name: &str,
+    parents: &[(ChildTable, String)],
+    deleted: &mut Vec<ChildTable>,
+) {
+    let mut children: Vec<&ChildTable> = parents
+        .iter()
+        .filter(|(_, parent)| parent == table_name)
+        .map(|(child, _)| child)
+        .collect();
+    children.sort_by(|a, b| a.table_name.cmp(&b.table_name));
+    for child in children {
+        collect_deleted_descendants(&child.table_name, parents, deleted);
+        let cascade = child.interleave_type.as_deref() == Some("IN PARENT")
+            && child.on_delete_action.as_deref() == Some("CASCADE");
+        if !cascade {
+            deleted.push(child.clone());
+        }
+    }
 }
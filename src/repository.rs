@@ -1,49 +1,79 @@
-use crate::model::{Column, Index, Table};
+use crate::model::{
+    CheckConstraint, Column, ForeignKey, ForeignKeyColumn, Index, IndexColumn, SpannerType, Table,
+};
 use google_cloud_spanner::client::Client;
 use google_cloud_spanner::statement::Statement;
+use std::collections::HashMap;
 
 pub struct TableRepository {
     client: Client,
     json: bool,
     default: bool,
+    /// Rust types of `PROTO` / `ENUM` columns keyed by the fully qualified proto type name
+    proto_types: HashMap<String, String>,
+}
+
+/// Parses the `-p` mappings of the form `NAME=RUST_TYPE`, e.g. `examples.music.SingerInfo=crate::pb::SingerInfo`.
+pub fn parse_proto_types<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+) -> anyhow::Result<HashMap<String, String>> {
+    let mut proto_types = HashMap::new();
+    for v in values {
+        match v.split_once('=') {
+            Some((name, rust_type)) => proto_types.insert(name.to_string(), rust_type.to_string()),
+            None => anyhow::bail!("invalid proto type mapping {}, expected NAME=RUST_TYPE", v),
+        };
+    }
+    Ok(proto_types)
 }
 
 impl TableRepository {
-    pub fn new(client: Client, json: bool, default: bool) -> Self {
+    pub fn new(
+        client: Client,
+        json: bool,
+        default: bool,
+        proto_types: HashMap<String, String>,
+    ) -> Self {
         Self {
             client,
             json,
             default,
+            proto_types,
         }
     }
 
     pub async fn read_all(&self) -> anyhow::Result<Vec<Table>> {
-        let stmt = Statement::new("SELECT TABLE_NAME, PARENT_TABLE_NAME FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = '' ORDER BY TABLE_NAME");
+        let stmt = Statement::new("SELECT TABLE_NAME, PARENT_TABLE_NAME, ON_DELETE_ACTION, INTERLEAVE_TYPE FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = '' ORDER BY TABLE_NAME");
         let mut tx = self.client.single().await?;
         let mut itr = tx.query(stmt).await?;
 
-        let mut table_names: Vec<(String, Option<String>)> = vec![];
+        let mut rows = vec![];
         while let Some(row) = itr.next().await? {
-            table_names.push((
-                row.column_by_name("TABLE_NAME")?,
-                row.column_by_name("PARENT_TABLE_NAME")?,
-            ));
+            rows.push(row);
         }
 
         let mut tables: Vec<Table> = vec![];
-        while let Some(table_name) = table_names.pop() {
-            let columns = self.read_columns(&table_name.0).await?;
-            let indexes = self.read_indexes(&table_name.0).await?;
-            let table = Table::new(
-                table_name.0,
-                table_name.1,
+        while let Some(row) = rows.pop() {
+            let table_name: String = row.column_by_name("TABLE_NAME")?;
+            let columns = self.read_columns(&table_name).await?;
+            let indexes = self.read_indexes(&table_name, &columns).await?;
+            let foreign_keys = self.read_foreign_keys(&table_name).await?;
+            let check_constraints = self.read_check_constraints(&table_name, &columns).await?;
+            let mut table = Table::new(
+                table_name,
+                row.column_by_name("PARENT_TABLE_NAME")?,
                 columns,
                 indexes,
+                foreign_keys,
+                check_constraints,
                 self.json,
                 self.default,
             );
+            table.on_delete_action = row.column_by_name("ON_DELETE_ACTION")?;
+            table.interleave_type = row.column_by_name("INTERLEAVE_TYPE")?;
             tables.push(table)
         }
+        Table::resolve_relations(&mut tables);
         log::info!("{} tables found", tables.len());
         Ok(tables)
     }
@@ -52,7 +82,7 @@ impl TableRepository {
         let mut stmt = Statement::new(
             "
             SELECT
-                c.COLUMN_NAME, c.ORDINAL_POSITION, c.IS_NULLABLE = 'YES' AS IS_NULLABLE, c.SPANNER_TYPE,
+                c.COLUMN_NAME, c.ORDINAL_POSITION, c.IS_NULLABLE = 'YES' AS IS_NULLABLE, c.SPANNER_TYPE, c.COLUMN_DEFAULT,
                 EXISTS (
                     SELECT 1 FROM INFORMATION_SCHEMA.COLUMN_OPTIONS oc
                     WHERE oc.OPTION_NAME = 'allow_commit_timestamp'
@@ -89,17 +119,39 @@ impl TableRepository {
                 row.column_by_name("IS_PRIMARY_KEY")?,
                 row.column_by_name("IS_GENERATED")?,
                 row.column_by_name("ALLOW_COMMIT_TIMESTAMP")?,
-            );
+                row.column_by_name("COLUMN_DEFAULT")?,
+            )
+            .with_rust_types(&self.proto_types);
+            if let SpannerType::Proto {
+                name,
+                rust_type: None,
+            }
+            | SpannerType::Enum {
+                name,
+                rust_type: None,
+            } = column.parsed_type.element()
+            {
+                log::warn!(
+                    "{}.{}: no Rust type is configured for {}",
+                    table_name,
+                    column.column_name,
+                    name
+                );
+            }
             columns.push(column)
         }
         Ok(columns)
     }
 
-    async fn read_indexes(&self, table_name: &str) -> anyhow::Result<Vec<Index>> {
+    async fn read_indexes(
+        &self,
+        table_name: &str,
+        columns: &[Column],
+    ) -> anyhow::Result<Vec<Index>> {
         let mut stmt = Statement::new(
             "\
             SELECT \
-                INDEX_NAME, IS_UNIQUE  \
+                INDEX_NAME, IS_UNIQUE, IS_NULL_FILTERED, PARENT_TABLE_NAME  \
 		    FROM  \
 		        INFORMATION_SCHEMA.INDEXES  \
 		    WHERE \
@@ -113,50 +165,168 @@ impl TableRepository {
         ",
         );
         stmt.add_param("table", &table_name);
-        let mut index_names: Vec<(String, bool)> = vec![];
+        let mut index_rows = vec![];
         let mut tx = self.client.single().await?;
         let mut itr = tx.query(stmt).await?;
         while let Some(row) = itr.next().await? {
-            index_names.push((
-                row.column_by_name("INDEX_NAME")?,
-                row.column_by_name("IS_UNIQUE")?,
-            ))
+            index_rows.push(row);
         }
 
         let mut indexes: Vec<Index> = vec![];
-        while let Some(index_name) = index_names.pop() {
+        while let Some(index_row) = index_rows.pop() {
+            let index_name: String = index_row.column_by_name("INDEX_NAME")?;
             let mut stmt = Statement::new(
                 "\
                 SELECT \
-                    ORDINAL_POSITION, COLUMN_NAME \
+                    ORDINAL_POSITION, COLUMN_NAME, COLUMN_ORDERING \
                 FROM \
                     INFORMATION_SCHEMA.INDEX_COLUMNS \
                 WHERE \
                     TABLE_SCHEMA = '' \
                 AND \
                     INDEX_NAME = @index AND TABLE_NAME = @table \
-                ORDER BY ORDINAL_POSITION
+                ORDER BY ORDINAL_POSITION, COLUMN_NAME
             ",
             );
             stmt.add_param("table", &table_name);
-            stmt.add_param("index", &index_name.0);
+            stmt.add_param("index", &index_name);
 
-            let mut index = Index {
-                index_name: index_name.0,
-                unique: index_name.1,
-                columns: vec![],
-            };
+            let mut index_columns = vec![];
+            let mut storing = vec![];
             let mut tx = self.client.single().await?;
             let mut itr = tx.query(stmt).await?;
             while let Some(row) = itr.next().await? {
-                let column = (
-                    row.column_by_name::<String>("COLUMN_NAME")?,
-                    row.column_by_name::<i64>("ORDINAL_POSITION")?,
-                );
-                index.columns.push(column);
+                let column_name: String = row.column_by_name("COLUMN_NAME")?;
+                // STORING columns have no position in the index key
+                match row.column_by_name::<Option<i64>>("ORDINAL_POSITION")? {
+                    Some(ordinal_position) => {
+                        let column = columns
+                            .iter()
+                            .find(|c| c.column_name == column_name)
+                            .ok_or_else(|| {
+                                anyhow::anyhow!("unknown column {} of {}", column_name, index_name)
+                            })?;
+                        let spanner_type = column.spanner_type.clone();
+                        let parsed_type = column.parsed_type.clone();
+                        index_columns.push(IndexColumn {
+                            column_name,
+                            ordinal_position,
+                            ordering: row
+                                .column_by_name::<Option<String>>("COLUMN_ORDERING")?
+                                .unwrap_or_else(|| "ASC".to_string()),
+                            spanner_type,
+                            parsed_type,
+                        })
+                    }
+                    None => storing.push(column_name),
+                }
             }
-            indexes.push(index);
+            indexes.push(Index::new(
+                index_name,
+                index_row.column_by_name("IS_UNIQUE")?,
+                index_row.column_by_name("IS_NULL_FILTERED")?,
+                index_row.column_by_name("PARENT_TABLE_NAME")?,
+                index_columns,
+                storing,
+                columns,
+            ));
         }
         Ok(indexes)
     }
+
+    async fn read_foreign_keys(&self, table_name: &str) -> anyhow::Result<Vec<ForeignKey>> {
+        let mut stmt = Statement::new(
+            "
+            SELECT
+                rc.CONSTRAINT_NAME, rc.DELETE_RULE, kcu.COLUMN_NAME,
+                ukcu.TABLE_NAME AS REFERENCED_TABLE_NAME, ukcu.COLUMN_NAME AS REFERENCED_COLUMN_NAME
+            FROM
+                INFORMATION_SCHEMA.REFERENTIAL_CONSTRAINTS rc
+            INNER JOIN
+                INFORMATION_SCHEMA.KEY_COLUMN_USAGE kcu
+            ON
+                kcu.CONSTRAINT_SCHEMA = rc.CONSTRAINT_SCHEMA
+            AND
+                kcu.CONSTRAINT_NAME = rc.CONSTRAINT_NAME
+            INNER JOIN
+                INFORMATION_SCHEMA.KEY_COLUMN_USAGE ukcu
+            ON
+                ukcu.CONSTRAINT_SCHEMA = rc.UNIQUE_CONSTRAINT_SCHEMA
+            AND
+                ukcu.CONSTRAINT_NAME = rc.UNIQUE_CONSTRAINT_NAME
+            AND
+                ukcu.ORDINAL_POSITION = kcu.POSITION_IN_UNIQUE_CONSTRAINT
+            WHERE
+                rc.CONSTRAINT_SCHEMA = ''
+            AND
+                kcu.TABLE_NAME = @table
+            ORDER BY
+                rc.CONSTRAINT_NAME, kcu.ORDINAL_POSITION",
+        );
+        stmt.add_param("table", &table_name);
+        let mut foreign_keys: Vec<ForeignKey> = vec![];
+        let mut tx = self.client.single().await?;
+        let mut itr = tx.query(stmt).await?;
+        while let Some(row) = itr.next().await? {
+            let constraint_name = row.column_by_name::<String>("CONSTRAINT_NAME")?;
+            let column = ForeignKeyColumn {
+                column_name: row.column_by_name("COLUMN_NAME")?,
+                referenced_column_name: row.column_by_name("REFERENCED_COLUMN_NAME")?,
+            };
+            match foreign_keys.last_mut() {
+                Some(fk) if fk.constraint_name == constraint_name => fk.columns.push(column),
+                _ => foreign_keys.push(ForeignKey {
+                    constraint_name,
+                    table_name: table_name.to_string(),
+                    columns: vec![column],
+                    referenced_table_name: row.column_by_name("REFERENCED_TABLE_NAME")?,
+                    on_delete: row.column_by_name("DELETE_RULE")?,
+                    interleaved: false,
+                }),
+            }
+        }
+        Ok(foreign_keys)
+    }
+
+    async fn read_check_constraints(
+        &self,
+        table_name: &str,
+        columns: &[Column],
+    ) -> anyhow::Result<Vec<CheckConstraint>> {
+        let mut stmt = Statement::new(
+            "
+            SELECT
+                tc.CONSTRAINT_NAME, cc.CHECK_CLAUSE
+            FROM
+                INFORMATION_SCHEMA.TABLE_CONSTRAINTS tc
+            INNER JOIN
+                INFORMATION_SCHEMA.CHECK_CONSTRAINTS cc
+            ON
+                cc.CONSTRAINT_SCHEMA = tc.CONSTRAINT_SCHEMA
+            AND
+                cc.CONSTRAINT_NAME = tc.CONSTRAINT_NAME
+            WHERE
+                tc.TABLE_SCHEMA = ''
+            AND
+                tc.TABLE_NAME = @table
+            AND
+                tc.CONSTRAINT_TYPE = 'CHECK'
+            AND
+                NOT STARTS_WITH(tc.CONSTRAINT_NAME, 'CK_IS_NOT_NULL_')
+            ORDER BY
+                tc.CONSTRAINT_NAME",
+        );
+        stmt.add_param("table", &table_name);
+        let mut check_constraints: Vec<CheckConstraint> = vec![];
+        let mut tx = self.client.single().await?;
+        let mut itr = tx.query(stmt).await?;
+        while let Some(row) = itr.next().await? {
+            check_constraints.push(CheckConstraint::new(
+                row.column_by_name("CONSTRAINT_NAME")?,
+                row.column_by_name("CHECK_CLAUSE")?,
+                columns,
+            ));
+        }
+        Ok(check_constraints)
+    }
 }
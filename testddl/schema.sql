@@ -21,11 +21,27 @@ CREATE TABLE User
     UpdatedAt TIMESTAMP NOT NULL OPTIONS (allow_commit_timestamp=true)
 ) PRIMARY KEY(UserId);
 
+CREATE TABLE Item (
+    ItemId INT64 NOT NULL,
+    Name STRING(MAX) NOT NULL,
+    Price INT64 NOT NULL DEFAULT (100),
+    Tradable BOOL DEFAULT (TRUE),
+    CreatedAt TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP()),
+    Tags ARRAY<STRING(16)>,
+    Score FLOAT32 DEFAULT (0.5),
+    Embedding ARRAY<FLOAT32>(vector_length=>3),
+) PRIMARY KEY(ItemId);
+
+CREATE UNIQUE NULL_FILTERED INDEX IDX_Item_Name ON Item(Name) STORING (Price, Tradable, CreatedAt, Tags);
+
+CREATE INDEX IDX_Item_Price ON Item(Price DESC);
+
 CREATE TABLE UserItem (
     UserId STRING(36) NOT NULL,
     ItemId INT64 NOT NULL,
     Quantity INT64 NOT NULL,
-    UpdatedAt TIMESTAMP NOT NULL OPTIONS(allow_commit_timestamp=true)
+    UpdatedAt TIMESTAMP NOT NULL OPTIONS(allow_commit_timestamp=true),
+    CONSTRAINT FK_UserItem_Item FOREIGN KEY (ItemId) REFERENCES Item (ItemId)
 ) PRIMARY KEY(UserId, ItemId), INTERLEAVE IN PARENT User ON DELETE CASCADE;
 
 CREATE TABLE UserCharacter (
@@ -34,5 +50,49 @@ CREATE TABLE UserCharacter (
     Level INT64 NOT NULL,
     Equipment ARRAY<INT64> NOT NULL,
     AcquiredAt TIMESTAMP NOT NULL,
+    UpdatedAt TIMESTAMP NOT NULL OPTIONS(allow_commit_timestamp=true),
+    Rank INT64,
+    CONSTRAINT CK_UserCharacter_Level CHECK (Level BETWEEN 0 AND 100),
+    CONSTRAINT CK_UserCharacter_Equipment CHECK (ARRAY_LENGTH(Equipment) <= 5),
+    CONSTRAINT CK_UserCharacter_Rank CHECK (Rank > 0 AND CharacterId >= 0),
+    CONSTRAINT FK_UserCharacter_User FOREIGN KEY (UserId) REFERENCES User (UserId)
+) PRIMARY KEY(UserId, CharacterId), INTERLEAVE IN PARENT User ON DELETE NO ACTION;
+
+CREATE TABLE UserCharacterSkill (
+    UserId STRING(36) NOT NULL,
+    CharacterId INT64 NOT NULL,
+    SkillId INT64 NOT NULL,
+    SkillLevel INT64 NOT NULL,
+) PRIMARY KEY(UserId, CharacterId, SkillId), INTERLEAVE IN PARENT UserCharacter ON DELETE NO ACTION;
+
+CREATE TABLE GameConfig (
+    MaintenanceMode BOOL NOT NULL,
     UpdatedAt TIMESTAMP NOT NULL OPTIONS(allow_commit_timestamp=true)
-) PRIMARY KEY(UserId, CharacterId), INTERLEAVE IN PARENT User ON DELETE CASCADE;
\ No newline at end of file
+) PRIMARY KEY ();
+
+CREATE TABLE Trade (
+    TradeId INT64 NOT NULL,
+    SellerId STRING(36) NOT NULL,
+    BuyerId STRING(36) NOT NULL,
+    TradedAt TIMESTAMP NOT NULL OPTIONS(allow_commit_timestamp=true),
+    CONSTRAINT FK_Trade_Seller FOREIGN KEY (SellerId) REFERENCES User (UserId),
+    CONSTRAINT FK_Trade_Buyer FOREIGN KEY (BuyerId) REFERENCES User (UserId)
+) PRIMARY KEY(TradeId);
+
+CREATE TABLE Coupon (
+    Code STRING(16),
+    Amount INT64 NOT NULL,
+) PRIMARY KEY(Code);
+
+CREATE PROTO BUNDLE (
+    examples.music.SingerInfo,
+    examples.music.Genre,
+);
+
+CREATE TABLE Singer (
+    SingerId INT64 NOT NULL,
+    Info examples.music.SingerInfo NOT NULL,
+    Genre examples.music.Genre,
+    Infos ARRAY<examples.music.SingerInfo>,
+    Genres ARRAY<examples.music.Genre> NOT NULL,
+) PRIMARY KEY(SingerId);
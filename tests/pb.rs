@@ -0,0 +1,15 @@
+//! prost types of testddl/singer.proto for the PROTO and ENUM columns of `Singer`.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SingerInfo {
+    #[prost(string, tag = "1")]
+    pub name: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum Genre {
+    Unspecified = 0,
+    Pop = 1,
+    Jazz = 2,
+}
@@ -1,8 +1,24 @@
 mod gen;
+mod pb;
 
-use gen::user;
+use gen::{
+    coupon, game_config, item, singer, trade, user, user_character, user_character_skill, user_item,
+};
+use google_cloud_spanner::client::{Client, ClientConfig};
+use nene::model::SpannerType;
+use nene::repository::parse_proto_types;
 use std::env;
 
+// The tests run in parallel against one database, so each test writes only its own rows:
+// user ids are named after the test and item ids lie in a block of 100 reserved for the test.
+
+async fn client() -> Client {
+    let database = env::var("SPANNER_DSN").unwrap();
+    Client::new(database, ClientConfig::default())
+        .await
+        .unwrap()
+}
+
 #[tokio::test]
 async fn test_generated() {
     let v = user::User {
@@ -10,11 +26,7 @@ async fn test_generated() {
         ..Default::default()
     };
 
-    let database = env::var("SPANNER_DSN").unwrap();
-    let config = google_cloud_spanner::client::ClientConfig::default();
-    let client = google_cloud_spanner::client::Client::new(database, config)
-        .await
-        .unwrap();
+    let client = client().await;
     let _ = client.apply(vec![v.insert_or_update()]).await.unwrap();
     let mut tx = client.single().await.unwrap();
     let user = user::User::find_by_pk(&mut tx, "test_user", None)
@@ -29,3 +41,1015 @@ async fn test_generated() {
     assert_eq!(dser.not_null_date, v.not_null_date);
     assert_eq!(dser.not_null_timestamp, v.not_null_timestamp);
 }
+
+#[tokio::test]
+async fn test_generated_singleton() {
+    let v = game_config::GameConfig {
+        maintenance_mode: true,
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let _ = client.apply(vec![v.insert_or_update()]).await.unwrap();
+    let mut tx = client.single().await.unwrap();
+    let config = game_config::GameConfig::get(&mut tx, None).await.unwrap();
+    assert!(config.unwrap().maintenance_mode);
+
+    let _ = client.apply(vec![v.delete()]).await.unwrap();
+    let mut tx = client.single().await.unwrap();
+    let config = game_config::GameConfig::get(&mut tx, None).await.unwrap();
+    assert!(config.is_none());
+}
+
+#[tokio::test]
+async fn test_generated_foreign_key() {
+    let u = user::User {
+        user_id: "test_fk_user".to_string(),
+        ..Default::default()
+    };
+    let i = item::Item {
+        item_id: 100,
+        name: "test_item".to_string(),
+        ..Default::default()
+    };
+    let ui = user_item::UserItem {
+        user_id: u.user_id.clone(),
+        item_id: i.item_id,
+        quantity: 10,
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let _ = client
+        .apply(vec![
+            u.insert_or_update(),
+            i.insert_or_update(),
+            ui.insert_or_update(),
+        ])
+        .await
+        .unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let fetched = ui.fetch_item_by_item_id(&mut tx, None).await.unwrap();
+    assert_eq!(fetched.unwrap().name, i.name);
+    let user_items = i
+        .read_user_item_rows_by_item_id(&mut tx, None)
+        .await
+        .unwrap();
+    assert!(user_items.iter().any(|v| v.user_id == u.user_id));
+}
+
+#[tokio::test]
+async fn test_generated_foreign_keys_to_same_table() {
+    let seller = user::User {
+        user_id: "test_trade_seller".to_string(),
+        ..Default::default()
+    };
+    let buyer = user::User {
+        user_id: "test_trade_buyer".to_string(),
+        ..Default::default()
+    };
+    let t = trade::Trade {
+        trade_id: 1,
+        seller_id: seller.user_id.clone(),
+        buyer_id: buyer.user_id.clone(),
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let _ = client
+        .apply(vec![
+            seller.insert_or_update(),
+            buyer.insert_or_update(),
+            t.insert_or_update(),
+        ])
+        .await
+        .unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let fetched = t.fetch_user_by_seller_id(&mut tx, None).await.unwrap();
+    assert_eq!(fetched.unwrap().user_id, seller.user_id);
+    let fetched = t.fetch_user_by_buyer_id(&mut tx, None).await.unwrap();
+    assert_eq!(fetched.unwrap().user_id, buyer.user_id);
+    let sold = seller
+        .read_trade_rows_by_seller_id(&mut tx, None)
+        .await
+        .unwrap();
+    assert_eq!(sold.len(), 1);
+    let bought = seller
+        .read_trade_rows_by_buyer_id(&mut tx, None)
+        .await
+        .unwrap();
+    assert!(bought.is_empty());
+}
+
+#[tokio::test]
+async fn test_generated_interleave() {
+    let u = user::User {
+        user_id: "test_interleave_user".to_string(),
+        ..Default::default()
+    };
+    let uc = user_character::UserCharacter {
+        user_id: u.user_id.clone(),
+        character_id: 1,
+        level: 5,
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let _ = client
+        .apply(vec![u.insert_or_update(), uc.insert_or_update()])
+        .await
+        .unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let characters = u.read_user_character_rows(&mut tx, None).await.unwrap();
+    assert_eq!(characters.len(), 1);
+    let parent = uc.fetch_user(&mut tx, None).await.unwrap();
+    assert_eq!(parent.unwrap().user_id, u.user_id);
+
+    let loaded = user::User::find_with_children(&mut tx, &u.user_id, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(loaded.user.user_id, u.user_id);
+    assert_eq!(loaded.user_character_rows.len(), 1);
+    assert_eq!(loaded.user_character_rows[0].level, uc.level);
+    assert!(loaded.user_item_rows.is_empty());
+}
+
+#[tokio::test]
+async fn test_generated_delete_with_children() {
+    let u = user::User {
+        user_id: "test_delete_with_children_user".to_string(),
+        ..Default::default()
+    };
+    let uc = user_character::UserCharacter {
+        user_id: u.user_id.clone(),
+        character_id: 1,
+        ..Default::default()
+    };
+    // User -> UserCharacter -> UserCharacterSkill are interleaved with ON DELETE NO ACTION
+    let skill = user_character_skill::UserCharacterSkill {
+        user_id: u.user_id.clone(),
+        character_id: uc.character_id,
+        skill_id: 1,
+        skill_level: 3,
+    };
+
+    let client = client().await;
+    let _ = client
+        .apply(vec![
+            u.insert_or_update(),
+            uc.insert_or_update(),
+            skill.insert_or_update(),
+        ])
+        .await
+        .unwrap();
+    assert!(client.apply(vec![u.delete()]).await.is_err());
+    let _ = client.apply(u.delete_with_children()).await.unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let user = user::User::find_by_pk(&mut tx, &u.user_id, None)
+        .await
+        .unwrap();
+    assert!(user.is_none());
+    let characters = user_character::UserCharacter::read_by_user_id(&mut tx, &u.user_id, None)
+        .await
+        .unwrap();
+    assert!(characters.is_empty());
+    let skills =
+        user_character_skill::UserCharacterSkill::read_by_user_id(&mut tx, &u.user_id, None)
+            .await
+            .unwrap();
+    assert!(skills.is_empty());
+}
+
+#[tokio::test]
+async fn test_generated_column_default() {
+    let i = item::Item {
+        item_id: 200,
+        name: "test_default_item".to_string(),
+        price: 200,
+        ..Default::default()
+    };
+    assert_eq!(item::Item::default().price, 100);
+    assert_eq!(item::Item::default().tradable, Some(true));
+
+    let client = client().await;
+    let _ = client
+        .apply(vec![i.delete(), i.insert_without_defaults()])
+        .await
+        .unwrap();
+    let mut tx = client.single().await.unwrap();
+    let inserted = item::Item::find_by_pk(&mut tx, &i.item_id, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(inserted.price, 100);
+    assert_eq!(inserted.tradable, Some(true));
+}
+
+#[test]
+fn test_generated_check_constraint() {
+    let mut uc = user_character::UserCharacter {
+        level: 100,
+        ..Default::default()
+    };
+    assert!(uc.validate().is_ok());
+    uc.level = 101;
+    assert_eq!(
+        uc.validate(),
+        Err(gen::ValidationError::Check {
+            table: user_character::TABLE_NAME,
+            constraint: "CK_UserCharacter_Level",
+        })
+    );
+}
+
+#[test]
+fn test_generated_check_constraint_nullable() {
+    // Rank > 0 AND CharacterId >= 0
+    let mut uc = user_character::UserCharacter {
+        character_id: 1,
+        rank: None,
+        ..Default::default()
+    };
+    assert!(uc.validate().is_ok());
+    uc.rank = Some(3);
+    assert!(uc.validate().is_ok());
+    uc.rank = Some(0);
+    let violation = Err(gen::ValidationError::Check {
+        table: user_character::TABLE_NAME,
+        constraint: "CK_UserCharacter_Rank",
+    });
+    assert_eq!(uc.validate(), violation);
+    // NULL AND FALSE is FALSE
+    uc.rank = None;
+    uc.character_id = -1;
+    assert_eq!(uc.validate(), violation);
+}
+
+#[test]
+fn test_generated_max_length() {
+    let mut u = user::User {
+        user_id: "a".repeat(36),
+        nullable_string: Some("あ".repeat(100)),
+        ..Default::default()
+    };
+    assert!(u.validate().is_ok());
+    u.nullable_string = Some("あ".repeat(101));
+    assert_eq!(
+        u.validate(),
+        Err(gen::ValidationError::MaxLength {
+            table: user::TABLE_NAME,
+            column: user::COLUMN_NULLABLE_STRING,
+            max: 100,
+            actual: 101,
+        })
+    );
+}
+
+#[test]
+fn test_generated_array_max_length() {
+    let mut i = item::Item {
+        tags: Some(vec!["a".repeat(16)]),
+        ..Default::default()
+    };
+    assert!(i.validate().is_ok());
+    i.tags = Some(vec!["a".to_string(), "b".repeat(17)]);
+    assert_eq!(
+        i.validate(),
+        Err(gen::ValidationError::MaxLength {
+            table: item::TABLE_NAME,
+            column: item::COLUMN_TAGS,
+            max: 16,
+            actual: 17,
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_generated_index() {
+    let i = item::Item {
+        item_id: 300,
+        name: "test_index_item".to_string(),
+        price: 300,
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let _ = client.apply(vec![i.insert_or_update()]).await.unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let found = item::Item::find_by_idx_item_name(&mut tx, &i.name, None)
+        .await
+        .unwrap();
+    assert_eq!(found.unwrap().item_id, i.item_id);
+    let items = item::Item::read_by_idx_item_price(&mut tx, &i.price, None)
+        .await
+        .unwrap();
+    assert!(items.iter().any(|v| v.item_id == i.item_id));
+}
+
+#[tokio::test]
+async fn test_generated_key_range() {
+    let u = user::User {
+        user_id: "test_key_range_user".to_string(),
+        ..Default::default()
+    };
+    let characters: Vec<user_character::UserCharacter> = (1..=3)
+        .map(|character_id| user_character::UserCharacter {
+            user_id: u.user_id.clone(),
+            character_id,
+            ..Default::default()
+        })
+        .collect();
+
+    let client = client().await;
+    let mut mutations = vec![u.insert_or_update()];
+    mutations.extend(characters.iter().map(|v| v.insert_or_update()));
+    let _ = client.apply(mutations).await.unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let prefix = google_cloud_spanner::key::Key::new(&u.user_id);
+    let all = user_character::UserCharacter::read_prefix(&mut tx, prefix, None)
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 3);
+    let from = google_cloud_spanner::key::Key::composite(&[&u.user_id, &1]);
+    let to = google_cloud_spanner::key::Key::composite(&[&u.user_id, &3]);
+    let range = user_character::UserCharacter::read_range(&mut tx, from, to, 0, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        range.iter().map(|v| v.character_id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+}
+
+#[tokio::test]
+async fn test_generated_primary_key() {
+    let u = user::User {
+        user_id: "test/primary%key".to_string(),
+        ..Default::default()
+    };
+    let uc = user_character::UserCharacter {
+        user_id: u.user_id.clone(),
+        character_id: 7,
+        ..Default::default()
+    };
+    let key = uc.key();
+    let formatted = key.to_string();
+    assert_eq!(formatted, "test%2Fprimary%25key/7");
+    assert_eq!(
+        formatted.parse::<user_character::UserCharacterKey>(),
+        Ok(key.clone())
+    );
+    assert!("test/1/2"
+        .parse::<user_character::UserCharacterKey>()
+        .is_err());
+
+    let client = client().await;
+    let _ = client
+        .apply(vec![u.insert_or_update(), uc.insert_or_update()])
+        .await
+        .unwrap();
+    let mut tx = client.single().await.unwrap();
+    let found = user_character::UserCharacter::find_by_key(&mut tx, &key, None)
+        .await
+        .unwrap();
+    assert_eq!(found.unwrap().key(), key);
+    let _ = client
+        .apply(vec![user_character::UserCharacter::delete_by_key(&key)])
+        .await
+        .unwrap();
+    let mut tx = client.single().await.unwrap();
+    let found = user_character::UserCharacter::find_by_key(&mut tx, &key, None)
+        .await
+        .unwrap();
+    assert!(found.is_none());
+}
+
+#[tokio::test]
+async fn test_generated_nullable_primary_key() {
+    let c = coupon::Coupon {
+        code: None,
+        amount: 500,
+    };
+
+    let client = client().await;
+    let _ = client.apply(vec![c.insert_or_update()]).await.unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let found = coupon::Coupon::find_by_key(&mut tx, &c.key(), None)
+        .await
+        .unwrap();
+    assert_eq!(found.unwrap().amount, c.amount);
+    let missing = coupon::CouponKey {
+        code: Some("test_missing".to_string()),
+    };
+    let found = coupon::Coupon::find_by_key(&mut tx, &missing, None)
+        .await
+        .unwrap();
+    assert!(found.is_none());
+}
+
+#[tokio::test]
+async fn test_generated_find_by_pks() {
+    let users: Vec<user::User> = ["test_find_by_pks_1", "test_find_by_pks_2"]
+        .iter()
+        .map(|user_id| user::User {
+            user_id: user_id.to_string(),
+            ..Default::default()
+        })
+        .collect();
+
+    let client = client().await;
+    let _ = client
+        .apply(users.iter().map(|v| v.insert_or_update()).collect())
+        .await
+        .unwrap();
+    let mut keys: Vec<user::UserKey> = users.iter().map(|v| v.key()).collect();
+    keys.push(user::UserKey {
+        user_id: "test_find_by_pks_missing".to_string(),
+    });
+    let mut tx = client.single().await.unwrap();
+    let found = user::User::find_by_pks(&mut tx, &keys, None).await.unwrap();
+    assert_eq!(
+        found.iter().map(|v| v.key()).collect::<Vec<_>>(),
+        keys[..2].to_vec()
+    );
+}
+
+#[tokio::test]
+async fn test_generated_update_columns() {
+    let u = user::User {
+        user_id: "test_update_columns_user".to_string(),
+        ..Default::default()
+    };
+    let uc = user_character::UserCharacter {
+        user_id: u.user_id.clone(),
+        character_id: 1,
+        level: 1,
+        equipment: vec![1, 2],
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let _ = client
+        .apply(vec![u.insert_or_update(), uc.insert_or_update()])
+        .await
+        .unwrap();
+    let mutation = user_character::UserCharacter::update_columns(uc.key())
+        .set_level(5)
+        .set_updated_at_commit_ts()
+        .build();
+    let _ = client.apply(vec![mutation]).await.unwrap();
+    let mut tx = client.single().await.unwrap();
+    let updated = user_character::UserCharacter::find_by_key(&mut tx, &uc.key(), None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.level, 5);
+    assert_eq!(updated.equipment, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn test_generated_commit_timestamp() {
+    let mut u = user::User {
+        user_id: "test_commit_timestamp_user".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(u.updated_at, time::OffsetDateTime::UNIX_EPOCH);
+
+    let client = client().await;
+    let commit_timestamp = client
+        .apply(vec![u.insert_or_update()])
+        .await
+        .unwrap()
+        .unwrap();
+    u.set_commit_timestamp(&commit_timestamp);
+    let mut tx = client.single().await.unwrap();
+    let user = user::User::find_by_pk(&mut tx, &u.user_id, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.updated_at, u.updated_at);
+}
+
+#[tokio::test]
+async fn test_generated_delete_by_pk() {
+    let u = user::User {
+        user_id: "test_delete_by_pk_user".to_string(),
+        ..Default::default()
+    };
+    let characters: Vec<user_character::UserCharacter> = (1..=2)
+        .map(|character_id| user_character::UserCharacter {
+            user_id: u.user_id.clone(),
+            character_id,
+            ..Default::default()
+        })
+        .collect();
+
+    let client = client().await;
+    let mut mutations = vec![u.insert_or_update()];
+    mutations.extend(characters.iter().map(|v| v.insert_or_update()));
+    let _ = client.apply(mutations).await.unwrap();
+
+    let _ = client
+        .apply(vec![user_character::UserCharacter::delete_by_pk(
+            &u.user_id, &1,
+        )])
+        .await
+        .unwrap();
+    let mut tx = client.single().await.unwrap();
+    let rest = user_character::UserCharacter::read_by_user_id(&mut tx, &u.user_id, None)
+        .await
+        .unwrap();
+    assert_eq!(rest.len(), 1);
+
+    let _ = client
+        .apply(vec![user_character::UserCharacter::delete_by_user_id(
+            &u.user_id,
+        )])
+        .await
+        .unwrap();
+    let mut tx = client.single().await.unwrap();
+    let rest = user_character::UserCharacter::read_by_user_id(&mut tx, &u.user_id, None)
+        .await
+        .unwrap();
+    assert!(rest.is_empty());
+}
+
+#[tokio::test]
+async fn test_generated_dml() {
+    let mut i = item::Item {
+        item_id: 400,
+        name: "test_dml_item".to_string(),
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let mut tx = client.begin_read_write_transaction().await.unwrap();
+    let result: Result<Option<item::Item>, google_cloud_spanner::client::Error> = async {
+        let _ = tx
+            .batch_update(vec![i.delete_dml(), i.insert_dml()])
+            .await?;
+        i.price = 500;
+        let updated = tx.update(i.update_dml()).await?;
+        assert_eq!(updated, 1);
+        item::Item::find_by_pk(&mut tx, &i.item_id, None).await
+    }
+    .await;
+    let (_, updated) = tx.end(result, None).await.unwrap();
+    assert_eq!(updated.unwrap().price, 500);
+}
+
+#[tokio::test]
+async fn test_generated_dml_returning() {
+    let i = item::Item {
+        item_id: 900,
+        name: "test_dml_returning_item".to_string(),
+        price: 900,
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let _ = client.apply(vec![i.delete()]).await.unwrap();
+    let mut tx = client.begin_read_write_transaction().await.unwrap();
+    let result: Result<Option<item::Item>, google_cloud_spanner::client::Error> = async {
+        let mut reader = tx.query(i.insert_dml_returning()).await?;
+        match reader.next().await? {
+            Some(row) => Ok(Some(row.try_into()?)),
+            None => Ok(None),
+        }
+    }
+    .await;
+    let (_, inserted) = tx.end(result, None).await.unwrap();
+    let inserted = inserted.unwrap();
+    assert_eq!(inserted.item_id, i.item_id);
+    assert_eq!(inserted.price, i.price);
+}
+
+#[tokio::test]
+async fn test_generated_float32() {
+    let mut i = item::Item {
+        item_id: 500,
+        name: "test_float32_item".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(i.score, Some(0.5));
+    i.score = Some(0.25);
+    i.embedding = Some(vec![0.5, 1.0, -2.0]);
+
+    let client = client().await;
+    let _ = client.apply(vec![i.insert_or_update()]).await.unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let rows = item::Item::query()
+        .filter(item::col::ItemId.eq(i.item_id))
+        .filter(item::col::Score.lt(0.3))
+        .fetch(&mut tx, None)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].score, i.score);
+    assert_eq!(rows[0].embedding, i.embedding);
+
+    let mut tx = client.begin_read_write_transaction().await.unwrap();
+    let result: Result<Option<item::Item>, google_cloud_spanner::client::Error> = async {
+        i.score = Some(0.75);
+        i.embedding = Some(vec![1.5, 2.5, 3.5]);
+        let _ = tx.update(i.update_dml()).await?;
+        item::Item::find_by_pk(&mut tx, &i.item_id, None).await
+    }
+    .await;
+    let (_, updated) = tx.end(result, None).await.unwrap();
+    let updated = updated.unwrap();
+    assert_eq!(updated.score, i.score);
+    assert_eq!(updated.embedding, i.embedding);
+}
+
+#[tokio::test]
+async fn test_generated_partitioned_dml() {
+    let items: Vec<item::Item> = (600..603)
+        .map(|item_id| item::Item {
+            item_id,
+            name: format!("test_partitioned_dml_item_{}", item_id),
+            price: 1000,
+            ..Default::default()
+        })
+        .collect();
+
+    let client = client().await;
+    let _ = client
+        .apply(items.iter().map(|v| v.insert_or_update()).collect())
+        .await
+        .unwrap();
+    let updated = item::Item::partitioned_update(item::col::Tradable.set(false))
+        .set(item::col::Score.set(None))
+        .filter(item::col::Price.eq(1000))
+        .where_("ItemId BETWEEN 600 AND 699")
+        .execute(&client)
+        .await
+        .unwrap();
+    assert_eq!(updated, 3);
+    let deleted = item::Item::partitioned_delete()
+        .filter(item::col::Tradable.eq(false))
+        .where_("ItemId BETWEEN 600 AND 699")
+        .execute(&client)
+        .await
+        .unwrap();
+    assert_eq!(deleted, 3);
+}
+
+#[tokio::test]
+async fn test_generated_stream() {
+    let u = user::User {
+        user_id: "test_stream_user".to_string(),
+        ..Default::default()
+    };
+    let characters: Vec<user_character::UserCharacter> = (1..=3)
+        .map(|character_id| user_character::UserCharacter {
+            user_id: u.user_id.clone(),
+            character_id,
+            ..Default::default()
+        })
+        .collect();
+
+    let client = client().await;
+    let mut mutations = vec![u.insert_or_update()];
+    mutations.extend(characters.iter().map(|v| v.insert_or_update()));
+    let _ = client.apply(mutations).await.unwrap();
+
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let mut rows = u.stream_user_character_rows(&mut tx, None).await.unwrap();
+    let mut count = 0;
+    while let Some(character) = rows.next().await.unwrap() {
+        assert_eq!(character.user_id, u.user_id);
+        count += 1;
+    }
+    assert_eq!(count, 3);
+    drop(rows);
+
+    let mut rows = user_character::UserCharacter::stream_by_user_id(&mut tx, &u.user_id, None)
+        .await
+        .unwrap();
+    assert!(rows.next().await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_generated_page() {
+    let u = user::User {
+        user_id: "test_page_user".to_string(),
+        ..Default::default()
+    };
+    let characters: Vec<user_character::UserCharacter> = (1..=5)
+        .map(|character_id| user_character::UserCharacter {
+            user_id: u.user_id.clone(),
+            character_id,
+            ..Default::default()
+        })
+        .collect();
+
+    let client = client().await;
+    let mut mutations = vec![u.insert_or_update()];
+    mutations.extend(characters.iter().map(|v| v.insert_or_update()));
+    let _ = client.apply(mutations).await.unwrap();
+
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let mut after = None;
+    let mut pages = vec![];
+    loop {
+        let page = user_character::UserCharacter::read_page_by_user_id(
+            &mut tx,
+            &u.user_id,
+            after.as_ref(),
+            2,
+            None,
+        )
+        .await
+        .unwrap();
+        pages.push(page.rows.iter().map(|v| v.character_id).collect::<Vec<_>>());
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec![vec![1, 2], vec![3, 4], vec![5]]);
+
+    let page = user::User::read_page(&mut tx, None, 1, None).await.unwrap();
+    assert_eq!(page.rows.len(), 1);
+    assert!(page.next.is_some());
+}
+
+#[tokio::test]
+async fn test_generated_query() {
+    let users: Vec<user::User> = (1..=3)
+        .map(|i| user::User {
+            user_id: format!("test_query_user_{}", i),
+            not_null_int_64: 100 + i,
+            nullable_int_64: if i == 1 { None } else { Some(i) },
+            ..Default::default()
+        })
+        .collect();
+
+    let client = client().await;
+    let _ = client
+        .apply(users.iter().map(|v| v.insert_or_update()).collect())
+        .await
+        .unwrap();
+
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let rows = user::User::query()
+        .filter(user::col::UserId.ge("test_query_user_"))
+        .filter(user::col::UserId.lt("test_query_user_4"))
+        .filter(
+            user::col::NotNullInt64
+                .gt(101)
+                .or(user::col::NullableInt64.is_null()),
+        )
+        .order_by(user::col::UserId.desc())
+        .limit(10)
+        .fetch(&mut tx, None)
+        .await
+        .unwrap();
+    let ids: Vec<String> = rows.into_iter().map(|v| v.user_id).collect();
+    assert_eq!(
+        ids,
+        vec![
+            "test_query_user_3",
+            "test_query_user_2",
+            "test_query_user_1"
+        ]
+    );
+
+    let rows = user::User::query()
+        .filter(user::col::UserId.in_(vec![
+            "test_query_user_1".to_string(),
+            "test_query_user_2".to_string(),
+        ]))
+        .filter(!user::col::NullableInt64.is_null())
+        .fetch(&mut tx, None)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].user_id, "test_query_user_2");
+}
+
+#[tokio::test]
+async fn test_generated_columns() {
+    assert_eq!(item::ItemColumn::primary_key(), &[item::ItemColumn::ItemId]);
+    assert_eq!(item::ItemColumn::all().len(), item::COLUMNS.len());
+    assert_eq!(item::ItemColumn::Name.name(), item::COLUMN_NAME);
+    assert_eq!(item::ItemColumn::Tags.spanner_type(), "ARRAY<STRING(16)>");
+    assert_eq!(
+        user_character::UserCharacterColumn::primary_key(),
+        &[
+            user_character::UserCharacterColumn::UserId,
+            user_character::UserCharacterColumn::CharacterId
+        ]
+    );
+
+    let i = item::Item {
+        item_id: 700,
+        name: "test_columns_item".to_string(),
+        price: 500,
+        ..Default::default()
+    };
+
+    let client = client().await;
+    let _ = client.apply(vec![i.insert_or_update()]).await.unwrap();
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let row = item::Item::find_columns_by_key(
+        &mut tx,
+        &i.key(),
+        &[item::ItemColumn::Name, item::ItemColumn::Price],
+        None,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        row.column_by_name::<String>(item::COLUMN_NAME).unwrap(),
+        i.name
+    );
+    assert_eq!(
+        row.column_by_name::<i64>(item::COLUMN_PRICE).unwrap(),
+        i.price
+    );
+    assert!(row
+        .column_by_name::<Vec<String>>(item::COLUMN_TAGS)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_generated_count() {
+    let u = user::User {
+        user_id: "test_count_user".to_string(),
+        ..Default::default()
+    };
+    let characters: Vec<user_character::UserCharacter> = (1..=3)
+        .map(|character_id| user_character::UserCharacter {
+            user_id: u.user_id.clone(),
+            character_id,
+            ..Default::default()
+        })
+        .collect();
+
+    let client = client().await;
+    let mut mutations = vec![u.insert_or_update()];
+    mutations.extend(characters.iter().map(|v| v.insert_or_update()));
+    let _ = client.apply(mutations).await.unwrap();
+
+    let mut tx = client.read_only_transaction().await.unwrap();
+    let count = user_character::UserCharacter::count_by_user_id(&mut tx, &u.user_id, None)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+    let count = user_character::UserCharacter::count(&mut tx, None)
+        .await
+        .unwrap();
+    assert!(count >= 3);
+    assert!(
+        user_character::UserCharacter::exists_by_pk(&mut tx, &u.user_id, &2, None)
+            .await
+            .unwrap()
+    );
+    assert!(
+        !user_character::UserCharacter::exists_by_pk(&mut tx, &u.user_id, &4, None)
+            .await
+            .unwrap()
+    );
+}
+
+async fn rename_item<R: item::ItemRepository>(
+    repository: &mut R,
+    key: &item::ItemKey,
+    name: &str,
+) -> Result<bool, google_cloud_spanner::client::Error> {
+    match repository.find(key).await? {
+        Some(mut i) => {
+            i.name = name.to_string();
+            repository.update(&i).await?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tokio::test]
+async fn test_generated_in_memory_repository() {
+    use item::ItemRepository;
+
+    let i = item::Item {
+        item_id: 800,
+        name: "test_repository_item".to_string(),
+        ..Default::default()
+    };
+    let mut repository = item::InMemoryItemRepository::new(vec![i.clone()]);
+    assert!(repository.insert(&i).await.is_err());
+
+    assert!(rename_item(&mut repository, &i.key(), "renamed")
+        .await
+        .unwrap());
+    assert_eq!(repository.rows[&i.key()].name, "renamed");
+    let missing = item::ItemKey { item_id: 801 };
+    assert!(!rename_item(&mut repository, &missing, "renamed")
+        .await
+        .unwrap());
+
+    let found = repository
+        .find_many(&[missing.clone(), i.key()])
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    repository.delete(&i.key()).await.unwrap();
+    assert!(repository.find(&i.key()).await.unwrap().is_none());
+    assert!(repository.update(&i).await.is_err());
+}
+
+#[test]
+fn test_proto_types() {
+    let proto_types = parse_proto_types([
+        "examples.music.SingerInfo=crate::pb::SingerInfo",
+        "examples.music.Genre=crate::pb::Genre",
+    ])
+    .unwrap();
+    assert_eq!(
+        proto_types.get("examples.music.SingerInfo").unwrap(),
+        "crate::pb::SingerInfo"
+    );
+    assert!(parse_proto_types(["examples.music.SingerInfo"]).is_err());
+
+    let proto = SpannerType::from("PROTO<examples.music.SingerInfo>");
+    assert_eq!(
+        proto,
+        SpannerType::Proto {
+            name: "examples.music.SingerInfo".to_string(),
+            rust_type: None,
+        }
+    );
+    assert_eq!(
+        proto.with_rust_types(&proto_types),
+        SpannerType::Proto {
+            name: "examples.music.SingerInfo".to_string(),
+            rust_type: Some("crate::pb::SingerInfo".to_string()),
+        }
+    );
+
+    let array = SpannerType::from("ARRAY<ENUM<examples.music.Genre>>");
+    assert_eq!(
+        array.with_rust_types(&proto_types).element(),
+        &SpannerType::Enum {
+            name: "examples.music.Genre".to_string(),
+            rust_type: Some("crate::pb::Genre".to_string()),
+        }
+    );
+
+    let unknown = SpannerType::from("ENUM<examples.music.Unknown>");
+    assert_eq!(
+        unknown.with_rust_types(&proto_types),
+        SpannerType::Enum {
+            name: "examples.music.Unknown".to_string(),
+            rust_type: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_generated_proto() {
+    let mut s = singer::Singer {
+        singer_id: 1,
+        ..Default::default()
+    };
+    let info = pb::SingerInfo {
+        name: "test_proto_singer".to_string(),
+    };
+    s.set_info_message(&info);
+    s.set_genre_enum(Some(pb::Genre::Jazz));
+    s.set_infos_messages(Some(std::slice::from_ref(&info)));
+    s.set_genres_enums(&[pb::Genre::Pop, pb::Genre::Jazz]);
+
+    let client = client().await;
+    let _ = client.apply(vec![s.insert_or_update()]).await.unwrap();
+    let mut tx = client.single().await.unwrap();
+    let found = singer::Singer::find_by_pk(&mut tx, &s.singer_id, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.info_message().unwrap(), info);
+    assert_eq!(found.genre_enum(), Some(pb::Genre::Jazz));
+    assert_eq!(found.infos_messages().unwrap(), Some(vec![info.clone()]));
+    assert_eq!(
+        found.genres_enums(),
+        Some(vec![pb::Genre::Pop, pb::Genre::Jazz])
+    );
+
+    s.singer_id = 2;
+    s.set_genre_enum(None);
+    let mut tx = client.begin_read_write_transaction().await.unwrap();
+    let result: Result<Option<singer::Singer>, google_cloud_spanner::client::Error> = async {
+        let _ = tx.update(s.delete_dml()).await?;
+        let _ = tx.update(s.insert_dml()).await?;
+        singer::Singer::find_by_pk(&mut tx, &s.singer_id, None).await
+    }
+    .await;
+    let (_, inserted) = tx.end(result, None).await.unwrap();
+    let inserted = inserted.unwrap();
+    assert_eq!(inserted.info_message().unwrap(), info);
+    assert_eq!(inserted.genre_enum(), None);
+}